@@ -0,0 +1,189 @@
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+
+use axum::{extract::State, http::header, routing::get, Router};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tokio::{net::TcpListener, sync::RwLock};
+
+use crate::{daemon::wait_for_signal, error::Result, fetch::Fetcher, reminder::Moment, render::escape_ical_text, sink::Sink, state::StateStore, Bot, Notification, NotificationKind};
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CalendarConfig {
+    /// Where `calendar` writes the `.ics` file.
+    pub path: PathBuf,
+    /// Address `calendar --serve` listens on, the feed is at `/calendar.ics`.
+    pub listen: SocketAddr,
+    /// Minutes before each event to show an alarm, `0` alerts as it starts. Empty adds no alarms.
+    pub alarms_minutes: Vec<u32>,
+    /// Kinds put in the calendar, all of them when empty.
+    pub kinds: Vec<NotificationKind>,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("splatink.ics"),
+            listen: SocketAddr::from(([127, 0, 0, 1], 8080)),
+            alarms_minutes: vec![30],
+            kinds: vec![],
+        }
+    }
+}
+
+/// RFC 5545 asks for lines of at most 75 octets.
+const LINE_LIMIT: usize = 75;
+const PRODID: &str = concat!("-//", env!("CARGO_PKG_NAME"), "//", env!("CARGO_PKG_VERSION"), "//EN");
+const UID_DOMAIN: &str = "splatink";
+
+/// A single VEVENT, tricolor battles get one of their own next to their Splatfest.
+struct Event {
+    uid: String,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    summary: String,
+    description: String,
+}
+
+fn events(notification: &Notification) -> impl Iterator<Item = Event> + '_ {
+    let description = notification.facts().into_iter().map(|(name, value)| format!("{name}: {value}")).collect::<Vec<_>>().join("\n");
+    let main = Event {
+        uid: notification.key(),
+        start: notification.start(),
+        end: notification.end(),
+        summary: notification.to_string(),
+        description: description.clone(),
+    };
+    let tricolor = notification.moments().into_iter()
+        .filter(|(moment, _)| *moment == Moment::Tricolor)
+        .map(move |(_, at)| Event {
+            uid: format!("{}:tricolor", notification.key()),
+            start: at,
+            end: notification.end(),
+            summary: format!("Tricolor Battles: {notification}"),
+            description: description.clone(),
+        })
+    ;
+    std::iter::once(main).chain(tricolor)
+}
+
+/// An iCalendar document with the events of the `config`'s kinds.
+///
+/// UIDs come from [`Notification::key`], so calendar apps update events in place across refreshes.
+pub fn calendar(config: &CalendarConfig, notifications: &[Notification], now: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        format!("PRODID:{PRODID}"),
+        "CALSCALE:GREGORIAN".to_owned(),
+        "METHOD:PUBLISH".to_owned(),
+        "X-WR-CALNAME:Splatoon 3".to_owned(),
+    ];
+    let included = notifications.iter().filter(|notification| config.kinds.is_empty() || config.kinds.contains(&notification.kind()));
+    for event in included.flat_map(events) {
+        lines.extend([
+            "BEGIN:VEVENT".to_owned(),
+            format!("UID:{}@{UID_DOMAIN}", escape_ical_text(&event.uid)),
+            format!("DTSTAMP:{}", timestamp(now)),
+            format!("DTSTART:{}", timestamp(event.start)),
+            format!("DTEND:{}", timestamp(event.end)),
+            format!("SUMMARY:{}", escape_ical_text(&event.summary)),
+            format!("DESCRIPTION:{}", escape_ical_text(&event.description)),
+        ]);
+        for minutes in &config.alarms_minutes {
+            lines.extend([
+                "BEGIN:VALARM".to_owned(),
+                "ACTION:DISPLAY".to_owned(),
+                format!("DESCRIPTION:{}", escape_ical_text(&event.summary)),
+                format!("TRIGGER:-PT{minutes}M"),
+                "END:VALARM".to_owned(),
+            ]);
+        }
+        lines.push("END:VEVENT".to_owned());
+    }
+    lines.push("END:VCALENDAR".to_owned());
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Splits `line` into CRLF terminated lines of at most [`LINE_LIMIT`] octets, continuations starting with a space.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > LINE_LIMIT {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// Serves `bot`'s calendar at `/calendar.ics` until SIGINT or SIGTERM, refreshing it on the `[daemon]` interval.
+pub async fn serve<F, K, St>(bot: &Bot<F, K, St>) -> Result<()>
+where
+    F: Fetcher,
+    K: Sink,
+    St: StateStore,
+{
+    let feed = Arc::new(RwLock::new(bot.calendar().await?));
+    let app = Router::new()
+        .route("/calendar.ics", get(|State(feed): State<Arc<RwLock<String>>>| async move {
+            ([(header::CONTENT_TYPE, "text/calendar; charset=utf-8")], feed.read().await.clone())
+        }))
+        .with_state(feed.clone())
+    ;
+    let listener = TcpListener::bind(bot.config.calendar.listen).await?;
+    println!("Serving the calendar at http://{}/calendar.ics", listener.local_addr()?);
+    let refresh = async {
+        loop {
+            let next = bot.config.daemon.next_tick(Utc::now());
+            tokio::time::sleep((next - Utc::now()).to_std().unwrap_or(Duration::ZERO)).await;
+            // A failed refresh keeps serving the previous calendar
+            match bot.calendar().await {
+                Ok(calendar) => *feed.write().await = calendar,
+                Err(err) => eprintln!("Calendar refresh failed: {err}"),
+            }
+        }
+    };
+    tokio::select! {
+        res = axum::serve(listener, app).with_graceful_shutdown(wait_for_signal()) => res?,
+        () = refresh => {},
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{splatfest::splatfest_notification, splatfest_data::SplatfestData};
+
+    #[test]
+    fn splatfests_get_a_separate_tricolor_event_with_alarms() {
+        let data: SplatfestData = serde_json::from_str(include_str!("../tests/fixtures/festivals_new.json")).unwrap();
+        let fest = splatfest_notification(&data.fests()[0]);
+        let config = CalendarConfig { alarms_minutes: vec![0, 60], ..Default::default() };
+        let ics = calendar(&config, std::slice::from_ref(&fest), Utc::now());
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n") && ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.lines().all(|line| line.len() <= LINE_LIMIT));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert_eq!(ics.matches("TRIGGER:-PT60M").count(), 2);
+        assert!(ics.contains(&format!("UID:{}@{UID_DOMAIN}", escape_ical_text(&fest.key()))));
+        assert!(ics.contains(&format!("UID:{}:tricolor@{UID_DOMAIN}", escape_ical_text(&fest.key()))));
+    }
+
+    #[test]
+    fn long_lines_are_folded_between_characters() {
+        let line = format!("DESCRIPTION:{}", "é".repeat(60));
+        let folded = fold(&line);
+        assert!(folded.split("\r\n").all(|part| part.len() <= LINE_LIMIT));
+        assert_eq!(folded.replace("\r\n ", "").trim_end(), line);
+    }
+}
@@ -0,0 +1,374 @@
+use std::{collections::{HashMap, HashSet}, env, fs, path::{Path, PathBuf}};
+
+use reqwest::Url;
+use serde::Deserialize;
+
+use crate::{calendar::CalendarConfig, daemon::DaemonConfig, feed::FeedConfig, sink::Backend, error::{Error, Result}, pvp::PvpConfig, reminder::ReminderConfig, splatfest_data::Region, state::StateConfig, status::StatusConfig, watch::WatchlistConfig, Notification, NotificationKind};
+
+pub const CONFIG_PATH_ENV: &str = "SPLATINK_CONFIG";
+pub const DEFAULT_CONFIG_PATH: &str = "config.toml";
+const ENV_PREFIX: &str = "SPLATINK";
+const DEFAULT_DESTINATION: &str = "default";
+const ALERTS_DESTINATION: &str = "alerts";
+const SCHEDULES_URL: &str = r#"https://splatoon3.ink/data/schedules.json"#;
+const FESTIVALS_URL: &str = r#"https://splatoon3.ink/data/festivals.json"#;
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Shorthand for a single destination named `default` that receives every kind.
+    pub webhook_url: String,
+    /// Splatfest regions for the `default` destination.
+    pub regions: Vec<Region>,
+    pub destinations: Vec<Destination>,
+    pub kinds: HashMap<NotificationKind, KindConfig>,
+    pub daemon: DaemonConfig,
+    pub corrections: CorrectionConfig,
+    pub sending: SendingConfig,
+    pub reminders: ReminderConfig,
+    pub pvp: PvpConfig,
+    pub watchlist: WatchlistConfig,
+    pub sources: SourcesConfig,
+    pub calendar: CalendarConfig,
+    pub feed: FeedConfig,
+    pub status: StatusConfig,
+    pub state: StateConfig,
+    pub alerts: AlertsConfig,
+}
+
+/// Where the splatoon3.ink data is fetched from, e.g. a mirror or a local stand-in for tests.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct SourcesConfig {
+    pub schedules_url: String,
+    pub festivals_url: String,
+    /// Time a single request may take before it counts as failed.
+    pub timeout_secs: u64,
+    /// Times a timed out request or server error is tried again before the committed copy stands in.
+    pub retries: u32,
+    /// Wait before the first retry, doubled for every retry after and jittered by half either way.
+    pub backoff_ms: u64,
+}
+
+impl Default for SourcesConfig {
+    fn default() -> Self {
+        Self {
+            schedules_url: SCHEDULES_URL.to_owned(),
+            festivals_url: FESTIVALS_URL.to_owned(),
+            timeout_secs: 30,
+            retries: 2,
+            backoff_ms: 1000,
+        }
+    }
+}
+
+/// Where warnings about the bot itself go, as opposed to notifications.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertsConfig {
+    /// No alerts are sent when unset.
+    pub webhook_url: Option<String>,
+    pub backend: Backend,
+    /// Bearer token for the `matrix` backend.
+    pub access_token: Option<String>,
+    /// How long a source may go without live data before the alert is sent.
+    pub stale_after_minutes: u64,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url: None,
+            backend: Backend::default(),
+            access_token: None,
+            stale_after_minutes: 180,
+        }
+    }
+}
+
+impl AlertsConfig {
+    /// The alert webhook as a destination of its own, if one is set.
+    pub fn destination(&self) -> Option<Destination> {
+        Some(Destination {
+            name: ALERTS_DESTINATION.to_owned(),
+            webhook_url: self.webhook_url.clone()?,
+            backend: self.backend,
+            access_token: self.access_token.clone(),
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct SendingConfig {
+    /// Times a rate limited or transiently failing message is retried before it counts as failed.
+    pub max_retries: u32,
+    /// Wait before retrying a server or connection error, doubled for every retry after.
+    pub backoff_ms: u64,
+    /// Runs in a row a destination's retryable failures may keep a source's cache from being committed,
+    /// after that they only count as failed for that destination.
+    pub max_held_runs: u32,
+}
+
+impl Default for SendingConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            backoff_ms: 1000,
+            max_held_runs: 3,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorrectionConfig {
+    /// Post a correction when an announced rotation's weapons, stage or king guess change,
+    /// and remove the announcement of one that is cancelled.
+    pub enabled: bool,
+    /// Edit the original announcement into the correction instead of posting a new message.
+    pub edit_original: bool,
+}
+
+impl Default for CorrectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            edit_original: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Destination {
+    pub name: String,
+    pub webhook_url: String,
+    /// How `webhook_url` is delivered to, Discord unless set.
+    #[serde(default)]
+    pub backend: Backend,
+    /// Bearer token for the `matrix` backend.
+    #[serde(default)]
+    pub access_token: Option<String>,
+    /// Kinds sent to this destination, all of them when empty.
+    #[serde(default)]
+    pub kinds: Vec<NotificationKind>,
+    /// Regions whose Splatfests are sent to this destination, only `US` when empty.
+    #[serde(default)]
+    pub regions: Vec<Region>,
+    /// Role ID pinged instead of the per-kind role.
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub avatar: Option<String>,
+}
+
+/// Per-kind overrides, any field left out falls back to [`NotificationKind::default_style`].
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct KindConfig {
+    /// Role ID to mention, without the `<@&...>` wrapping.
+    pub role: Option<String>,
+    pub username: Option<String>,
+    pub avatar: Option<String>,
+    pub thumbnail: Option<String>,
+}
+
+/// Fully resolved presentation for a [`NotificationKind`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct KindStyle {
+    pub ping: Option<String>,
+    pub username: String,
+    pub avatar: Option<String>,
+    pub thumbnail: Option<String>,
+}
+
+impl Config {
+    /// Loads the config from `path`, `$SPLATINK_CONFIG` or `config.toml`, applies env overrides and validates it.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let explicit = path.map(Path::to_path_buf).or_else(|| env::var_os(CONFIG_PATH_ENV).map(PathBuf::from));
+        let path = explicit.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_PATH));
+        let mut config = if path.exists() {
+            Self::parse(&path, &fs::read_to_string(&path)?)?
+        } else if explicit.is_some() {
+            return Err(Error::Config(format!("config file {} does not exist", path.display())));
+        } else {
+            Self::default()
+        };
+        config.apply_env(|key| env::var(key).ok());
+        if !config.webhook_url.is_empty() {
+            config.destinations.insert(0, Destination {
+                name: DEFAULT_DESTINATION.to_owned(),
+                webhook_url: config.webhook_url.clone(),
+                regions: config.regions.clone(),
+                ..Default::default()
+            });
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn parse(path: &Path, text: &str) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Ok(serde_json::from_str(text)?),
+            Some("toml") | None => Ok(toml::from_str(text)?),
+            Some(ext) => Err(Error::Config(format!("unsupported config format `.{ext}`, expected `.toml` or `.json`"))),
+        }
+    }
+
+    /// Overrides values with `SPLATINK_WEBHOOK_URL`, `SPLATINK_<SOURCE>_URL` and `SPLATINK_<KIND>_<FIELD>` variables.
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) {
+        if let Some(url) = var(&format!("{ENV_PREFIX}_WEBHOOK_URL")) {
+            self.webhook_url = url;
+        }
+        if let Some(url) = var(&format!("{ENV_PREFIX}_SCHEDULES_URL")) {
+            self.sources.schedules_url = url;
+        }
+        if let Some(url) = var(&format!("{ENV_PREFIX}_FESTIVALS_URL")) {
+            self.sources.festivals_url = url;
+        }
+        for kind in NotificationKind::ALL {
+            let key = |field: &str| format!("{ENV_PREFIX}_{}_{field}", kind.env_name());
+            let overrides = [
+                var(&key("ROLE")),
+                var(&key("USERNAME")),
+                var(&key("AVATAR")),
+                var(&key("THUMBNAIL")),
+            ];
+            if overrides.iter().all(Option::is_none) {
+                continue;
+            }
+            let [role, username, avatar, thumbnail] = overrides;
+            let entry = self.kinds.entry(kind).or_default();
+            entry.role = role.or(entry.role.take());
+            entry.username = username.or(entry.username.take());
+            entry.avatar = avatar.or(entry.avatar.take());
+            entry.thumbnail = thumbnail.or(entry.thumbnail.take());
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.destinations.is_empty() {
+            return Err(Error::Config(format!(
+                "no destinations configured, add `[[destinations]]` or `webhook_url` to {DEFAULT_CONFIG_PATH}, or set {ENV_PREFIX}_WEBHOOK_URL"
+            )));
+        }
+        let mut names = HashSet::new();
+        for destination in &self.destinations {
+            let name = &destination.name;
+            if name.is_empty() {
+                return Err(Error::Config("every destination needs a non-empty `name`".to_owned()));
+            }
+            if !names.insert(name) {
+                return Err(Error::Config(format!("destination name `{name}` is used more than once")));
+            }
+            validate_url(&format!("destinations.{name}.webhook_url"), &destination.webhook_url)?;
+            if destination.backend == Backend::Matrix && destination.access_token.as_deref().is_none_or(str::is_empty) {
+                return Err(Error::Config(format!("destinations.{name}.access_token is required for the `matrix` backend")));
+            }
+            if let Some(role) = &destination.role {
+                validate_role(&format!("destinations.{name}.role"), role)?;
+            }
+            if let Some(avatar) = &destination.avatar {
+                validate_url(&format!("destinations.{name}.avatar"), avatar)?;
+            }
+        }
+        validate_url("sources.schedules_url", &self.sources.schedules_url)?;
+        validate_url("sources.festivals_url", &self.sources.festivals_url)?;
+        if self.sources.timeout_secs == 0 {
+            return Err(Error::Config("sources.timeout_secs must be greater than 0".to_owned()));
+        }
+        if let Some(url) = &self.alerts.webhook_url {
+            validate_url("alerts.webhook_url", url)?;
+            if self.alerts.backend == Backend::Matrix && self.alerts.access_token.as_deref().is_none_or(str::is_empty) {
+                return Err(Error::Config("alerts.access_token is required for the `matrix` backend".to_owned()));
+            }
+        }
+        if self.feed.max_entries == 0 {
+            return Err(Error::Config("feed.max_entries must be greater than 0".to_owned()));
+        }
+        if self.daemon.interval_minutes == 0 {
+            return Err(Error::Config("daemon.interval_minutes must be greater than 0".to_owned()));
+        }
+        for (index, subscription) in self.pvp.subscriptions.iter().enumerate() {
+            if let Some(role) = &subscription.role {
+                validate_role(&format!("pvp.subscriptions[{index}].role"), role)?;
+            }
+        }
+        for (index, entry) in self.watchlist.entries.iter().enumerate() {
+            if entry.weapon.is_none() && entry.stage.is_none() {
+                return Err(Error::Config(format!("watchlist.entries[{index}] needs a `weapon` or a `stage`")));
+            }
+            if let Some(role) = &entry.role {
+                validate_role(&format!("watchlist.entries[{index}].role"), role)?;
+            }
+        }
+        for (kind, kind_config) in &self.kinds {
+            if let Some(role) = &kind_config.role {
+                validate_role(&format!("kinds.{kind}.role"), role)?;
+            }
+            if let Some(avatar) = &kind_config.avatar {
+                validate_url(&format!("kinds.{kind}.avatar"), avatar)?;
+            }
+            if let Some(thumbnail) = &kind_config.thumbnail {
+                validate_url(&format!("kinds.{kind}.thumbnail"), thumbnail)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn style(&self, kind: NotificationKind) -> KindStyle {
+        let mut style = kind.default_style();
+        if let Some(kind_config) = self.kinds.get(&kind) {
+            style.ping = kind_config.role.as_ref().map(|role| format!("<@&{role}>"));
+            style.username = kind_config.username.clone().unwrap_or(style.username);
+            style.avatar = kind_config.avatar.clone().or(style.avatar);
+            style.thumbnail = kind_config.thumbnail.clone().or(style.thumbnail);
+        }
+        style
+    }
+}
+
+impl Destination {
+    pub fn accepts(&self, notification: &Notification) -> bool {
+        let kind = notification.kind();
+        let kind_accepted = self.kinds.is_empty() || self.kinds.contains(&kind);
+        kind_accepted && notification.regions().is_none_or(|regions| regions.iter().any(|region| self.regions().contains(region)))
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        match self.regions.as_slice() {
+            [] => &[Region::US],
+            regions => regions,
+        }
+    }
+
+    /// The kind's style from [`Config::style`] with this destination's overrides on top.
+    pub fn style(&self, config: &Config, kind: NotificationKind) -> KindStyle {
+        let mut style = config.style(kind);
+        if let Some(role) = &self.role {
+            style.ping = Some(format!("<@&{role}>"));
+        }
+        style.username = self.username.clone().unwrap_or(style.username);
+        style.avatar = self.avatar.clone().or(style.avatar);
+        style
+    }
+}
+
+fn validate_role(field: &str, role: &str) -> Result<()> {
+    if role.is_empty() || !role.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(Error::Config(format!("{field} must be a numeric role ID, got `{role}`")));
+    }
+    Ok(())
+}
+
+fn validate_url(field: &str, url: &str) -> Result<()> {
+    match Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(()),
+        Ok(parsed) => Err(Error::Config(format!("{field} must be an http(s) URL, got scheme `{}`", parsed.scheme()))),
+        Err(err) => Err(Error::Config(format!("{field} is not a valid URL ({err}): `{url}`"))),
+    }
+}
@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Deserialize;
+use tokio::sync::watch;
+
+use crate::{fetch::Fetcher, sink::Sink, state::StateStore, status, Bot, SOURCES};
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct DaemonConfig {
+    /// Minutes between polls, counted from 00:00 UTC so ticks land on rotation boundaries.
+    pub interval_minutes: u32,
+    /// Delay after each boundary, splatoon3.ink needs a moment to publish the new data.
+    pub offset_seconds: u32,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            interval_minutes: 120,
+            offset_seconds: 90,
+        }
+    }
+}
+
+impl DaemonConfig {
+    pub fn interval(&self) -> TimeDelta {
+        TimeDelta::minutes(self.interval_minutes.into())
+    }
+
+    /// First tick strictly after `now`.
+    pub fn next_tick(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let interval = self.interval();
+        let offset = TimeDelta::seconds(self.offset_seconds.into());
+        let midnight = now.date_naive().and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+        let elapsed = (now - midnight - offset).num_seconds().max(-1);
+        let ticks = elapsed.div_euclid(interval.num_seconds()) + 1;
+        midnight + offset + interval * ticks as i32
+    }
+}
+
+/// Runs `bot` on the `[daemon]` interval until SIGINT or SIGTERM.
+pub async fn run<F, K, St>(bot: &Bot<F, K, St>)
+where
+    F: Fetcher,
+    K: Sink,
+    St: StateStore + Clone + Send + Sync + 'static,
+{
+    let (shutdown_tx, mut shutdown_rx) = watch::channel(false);
+    tokio::spawn(async move {
+        wait_for_signal().await;
+        println!("Shutdown requested, finishing current run");
+        let _ = shutdown_tx.send(true);
+    });
+    if let Some(listen) = bot.config.status.listen {
+        let server = status::serve(listen, bot.metrics.clone(), bot.config.daemon.interval(), SOURCES, bot.store.clone(), shutdown_rx.clone());
+        tokio::spawn(async move {
+            if let Err(err) = server.await {
+                eprintln!("Status server failed: {err}");
+            }
+        });
+    }
+    loop {
+        // Runs aren't raced against the shutdown signal so in-flight sends always complete
+        let next_reminder = match bot.run_once().await {
+            Ok(summary) => {
+                print!("{summary}");
+                for (source, err) in &summary.fetch_errors {
+                    eprintln!("Fetching {source} failed: {err}");
+                }
+                summary.next_reminder
+            },
+            Err(err) => {
+                eprintln!("Run failed: {err}");
+                None
+            },
+        };
+        if *shutdown_rx.borrow() {
+            break;
+        }
+        let mut next = bot.config.daemon.next_tick(Utc::now());
+        // Wake up early for reminders that fall between polls
+        if let Some(reminder) = next_reminder {
+            next = next.min(reminder);
+        }
+        let wait = (next - Utc::now()).to_std().unwrap_or(Duration::ZERO);
+        println!("Next poll at {next}");
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {},
+            _ = shutdown_rx.changed() => break,
+        }
+    }
+}
+
+pub(crate) async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {},
+                    _ = sigterm.recv() => {},
+                }
+            },
+            Err(err) => {
+                eprintln!("Could not listen for SIGTERM: {err}");
+                let _ = tokio::signal::ctrl_c().await;
+            },
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
@@ -0,0 +1,235 @@
+use std::{collections::HashMap, hash::Hash};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    schedule_data::{bankaraSchedule, bigRunRotation, eggstraWorkRotation, eventSchedule, regularSchedule, salmonRunRotation, xSchedule},
+    splatfest_data::{splatfest, RegionalFest},
+};
+
+/// Stable key for an entry that survives upstream edits to its other fields.
+pub trait Identity {
+    type Id: Eq + Hash;
+    fn identity(&self) -> Self::Id;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change<T> {
+    Added(T),
+    Removed(T),
+    Changed { old: T, new: T },
+}
+
+/// Compares two snapshots by [`Identity`] rather than position.
+///
+/// Added and changed entries come first in the order of `new`, followed by removed entries in the order of `old`.
+pub fn diff<T: Identity + PartialEq + Clone>(old: &[T], new: &[T]) -> Vec<Change<T>> {
+    let old_by_id: HashMap<_, _> = old.iter().map(|entry| (entry.identity(), entry)).collect();
+    let new_ids: HashMap<_, _> = new.iter().map(|entry| (entry.identity(), ())).collect();
+    let upserts = new.iter().filter_map(|entry| match old_by_id.get(&entry.identity()) {
+        None => Some(Change::Added(entry.clone())),
+        Some(&old_entry) if old_entry != entry => Some(Change::Changed { old: old_entry.clone(), new: entry.clone() }),
+        Some(_) => None,
+    });
+    let removals = old.iter()
+        .filter(|entry| !new_ids.contains_key(&entry.identity()))
+        .map(|entry| Change::Removed(entry.clone()))
+    ;
+    upserts.chain(removals).collect()
+}
+
+/// Just the entries of `new` that `old` doesn't have.
+pub fn added<T: Identity + PartialEq + Clone>(old: &[T], new: &[T]) -> Vec<T> {
+    diff(old, new).into_iter().filter_map(|change| match change {
+        Change::Added(entry) => Some(entry),
+        _ => None,
+    }).collect()
+}
+
+/// Each schedule only ever has one rotation per time slot.
+pub type SlotId = (DateTime<Utc>, DateTime<Utc>);
+
+/// The stage isn't part of it, so a revised stage is a change to the rotation rather than a different one.
+impl Identity for salmonRunRotation {
+    type Id = SlotId;
+    fn identity(&self) -> Self::Id {
+        (self.startTime.to_utc(), self.endTime.to_utc())
+    }
+}
+
+impl Identity for bigRunRotation {
+    type Id = SlotId;
+    fn identity(&self) -> Self::Id {
+        (self.startTime.to_utc(), self.endTime.to_utc())
+    }
+}
+
+impl Identity for eggstraWorkRotation {
+    type Id = SlotId;
+    fn identity(&self) -> Self::Id {
+        (self.startTime.to_utc(), self.endTime.to_utc())
+    }
+}
+
+impl Identity for regularSchedule {
+    type Id = SlotId;
+    fn identity(&self) -> Self::Id {
+        (self.startTime.to_utc(), self.endTime.to_utc())
+    }
+}
+
+impl Identity for bankaraSchedule {
+    type Id = SlotId;
+    fn identity(&self) -> Self::Id {
+        (self.startTime.to_utc(), self.endTime.to_utc())
+    }
+}
+
+impl Identity for xSchedule {
+    type Id = SlotId;
+    fn identity(&self) -> Self::Id {
+        (self.startTime.to_utc(), self.endTime.to_utc())
+    }
+}
+
+/// The same Challenge can come back, so its first time period tells the runs apart.
+impl Identity for eventSchedule {
+    type Id = (String, Option<DateTime<Utc>>);
+    fn identity(&self) -> Self::Id {
+        (
+            self.leagueMatchSetting.leagueMatchEvent.id.clone(),
+            self.timePeriods.first().map(|period| period.startTime.to_utc()),
+        )
+    }
+}
+
+impl Identity for splatfest {
+    type Id = String;
+    fn identity(&self) -> Self::Id {
+        self.__splatoon3ink_id.clone()
+    }
+}
+
+impl Identity for RegionalFest {
+    type Id = String;
+    fn identity(&self) -> Self::Id {
+        self.fest.identity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{schedule_data::RotationData, splatfest_data::{Region, SplatfestData}};
+
+    fn schedules() -> (RotationData, RotationData) {
+        (
+            serde_json::from_str(include_str!("../tests/fixtures/schedules_old.json")).unwrap(),
+            serde_json::from_str(include_str!("../tests/fixtures/schedules_new.json")).unwrap(),
+        )
+    }
+
+    fn splatfests() -> (SplatfestData, SplatfestData) {
+        (
+            serde_json::from_str(include_str!("../tests/fixtures/festivals_old.json")).unwrap(),
+            serde_json::from_str(include_str!("../tests/fixtures/festivals_new.json")).unwrap(),
+        )
+    }
+
+    #[test]
+    fn identical_snapshots_have_no_changes() {
+        let (old, _) = schedules();
+        let nodes = &old.data.coopGroupingSchedule.regularSchedules.nodes;
+        assert!(diff(nodes, nodes).is_empty());
+    }
+
+    #[test]
+    fn king_guess_update_is_a_change_not_an_addition() {
+        let (old, new) = schedules();
+        let changes = diff(
+            &old.data.coopGroupingSchedule.regularSchedules.nodes,
+            &new.data.coopGroupingSchedule.regularSchedules.nodes,
+        );
+        let changed: Vec<_> = changes.iter().filter_map(|change| match change {
+            Change::Changed { old, new } => Some((old, new)),
+            _ => None,
+        }).collect();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].0.__splatoon3ink_king_salmonid_guess, "Cohozuna");
+        assert_eq!(changed[0].1.__splatoon3ink_king_salmonid_guess, "Horrorboros");
+    }
+
+    #[test]
+    fn added_and_removed_rotations_are_classified() {
+        let (old, new) = schedules();
+        let old_nodes = old.data.coopGroupingSchedule.regularSchedules.nodes;
+        let new_nodes = new.data.coopGroupingSchedule.regularSchedules.nodes;
+        // Dropping the first fresh rotation makes it look like it expired
+        let changes = diff(&old_nodes, &new_nodes[1..]);
+        assert_eq!(changes, vec![
+            Change::Added(new_nodes[3].clone()),
+            Change::Removed(old_nodes[0].clone()),
+        ]);
+    }
+
+    #[test]
+    fn reordering_is_not_a_change() {
+        let (old, _) = schedules();
+        let nodes = old.data.coopGroupingSchedule.regularSchedules.nodes;
+        let reversed: Vec<_> = nodes.iter().rev().cloned().collect();
+        assert!(diff(&nodes, &reversed).is_empty());
+    }
+
+    #[test]
+    fn new_big_run_and_eggstra_work_are_added() {
+        let (old, new) = schedules();
+        let big_runs = diff(
+            &old.data.coopGroupingSchedule.bigRunSchedules.nodes,
+            &new.data.coopGroupingSchedule.bigRunSchedules.nodes,
+        );
+        assert!(matches!(big_runs.as_slice(), [Change::Added(_)]));
+        let eggstra_works = diff(
+            &old.data.coopGroupingSchedule.teamContestSchedules.nodes,
+            &new.data.coopGroupingSchedule.teamContestSchedules.nodes,
+        );
+        assert!(matches!(eggstra_works.as_slice(), [Change::Added(_)]));
+    }
+
+    #[test]
+    fn splatfests_are_keyed_by_splatoon3ink_id() {
+        let (old, new) = splatfests();
+        let changes = diff(&old.US.data.festRecords.nodes, &new.US.data.festRecords.nodes);
+        assert_eq!(changes.len(), 2);
+        match &changes[0] {
+            Change::Added(fest) => assert_eq!(fest.__splatoon3ink_id, "9f8e7d6c5b4a3f2e"),
+            change => panic!("expected an added fest, got {change:?}"),
+        }
+        match &changes[1] {
+            Change::Changed { old, new } => {
+                assert_eq!(old.state, "SCHEDULED");
+                assert_eq!(new.state, "FIRST_HALF");
+            },
+            change => panic!("expected a changed fest, got {change:?}"),
+        }
+    }
+
+    #[test]
+    fn global_fests_are_merged_across_regions() {
+        let (_, new) = splatfests();
+        let fests = new.fests();
+        assert_eq!(fests.len(), 3);
+        assert!(fests.iter().all(|fest| fest.regions == Region::ALL));
+    }
+
+    #[test]
+    fn region_exclusive_fest_is_added_once_with_its_region() {
+        let (old, mut new) = splatfests();
+        for region in [&mut new.US, &mut new.EU, &mut new.AP] {
+            region.data.festRecords.nodes.retain(|fest| fest.__splatoon3ink_id != "9f8e7d6c5b4a3f2e");
+        }
+        let added = added(&old.fests(), &new.fests());
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].regions, [Region::JP]);
+        assert!(!added[0].is_global());
+    }
+}
@@ -1,5 +1,6 @@
-use std::{fmt::Display, string::FromUtf8Error};
+use std::{fmt::Display, string::FromUtf8Error, time::Duration};
 
+use reqwest::StatusCode;
 use serde::Deserialize;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -8,9 +9,22 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     Reqwest(reqwest::Error),
     SerdeJson(serde_json::Error),
+    /// A splatoon3.ink document broken outside its `nodes` lists, at the path given.
+    Schema(String, serde_json::Error),
     IO(std::io::Error),
     FromUTF8(FromUtf8Error),
     Discord(DiscordError),
+    /// A non-Discord backend answered with an error status.
+    Http(StatusCode, String),
+    /// A request got no answer within the configured time.
+    Timeout(Duration),
+    Toml(toml::de::Error),
+    Atom(atom_syndication::Error),
+    #[cfg(feature = "sqlite")]
+    Sqlite(rusqlite::Error),
+    Config(String),
+    /// Not sent because the named destination's webhook failed permanently earlier.
+    Disabled(String),
 }
 
 impl Display for Error {
@@ -18,9 +32,18 @@ impl Display for Error {
         match self {
             Error::Reqwest(e) => write!(f, "reqwest error: {e}"),
             Error::SerdeJson(e) => write!(f, "serde_json error: {e}"),
+            Error::Schema(path, e) => write!(f, "schema error at {path}: {e}"),
             Error::IO(e) => write!(f, "std::io error: {e}"),
             Error::FromUTF8(e) => write!(f, "FromUTF8 error: {e}"),
             Error::Discord(e) => write!(f, "discord error: {e}"),
+            Error::Http(status, body) => write!(f, "http error {status}: {body}"),
+            Error::Timeout(after) => write!(f, "timed out after {}s", after.as_secs_f64()),
+            Error::Toml(e) => write!(f, "toml error: {e}"),
+            Error::Atom(e) => write!(f, "atom error: {e}"),
+            #[cfg(feature = "sqlite")]
+            Error::Sqlite(e) => write!(f, "sqlite error: {e}"),
+            Error::Config(e) => write!(f, "config error: {e}"),
+            Error::Disabled(name) => write!(f, "destination `{name}` is disabled"),
         }
     }
 }
@@ -30,9 +53,18 @@ impl std::error::Error for Error {
         match self {
             Error::Reqwest(e) => Some(e),
             Error::SerdeJson(e) => Some(e),
+            Error::Schema(_, e) => Some(e),
             Error::IO(e) => Some(e),
             Error::FromUTF8(e) => Some(e),
             Error::Discord(e) => Some(e),
+            Error::Toml(e) => Some(e),
+            Error::Atom(e) => Some(e),
+            #[cfg(feature = "sqlite")]
+            Error::Sqlite(e) => Some(e),
+            Error::Http(..) |
+            Error::Timeout(_) |
+            Error::Config(_) |
+            Error::Disabled(_) => None,
         }
     }
 }
@@ -57,24 +89,187 @@ impl From<FromUtf8Error> for Error {
         Self::FromUTF8(value)
     }
 }
+impl From<toml::de::Error> for Error {
+    fn from(value: toml::de::Error) -> Self {
+        Self::Toml(value)
+    }
+}
+impl From<atom_syndication::Error> for Error {
+    fn from(value: atom_syndication::Error) -> Self {
+        Self::Atom(value)
+    }
+}
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for Error {
+    fn from(value: rusqlite::Error) -> Self {
+        Self::Sqlite(value)
+    }
+}
 impl From<DiscordError> for Error {
     fn from(value: DiscordError) -> Self {
         Self::Discord(value)
     }
 }
 
-#[allow(dead_code)]
-#[derive(Deserialize, Debug)]
+/// Discord's JSON error code for a message that no longer exists, the webhook itself is fine.
+const UNKNOWN_MESSAGE: u64 = 10008;
+
+/// A Discord request that didn't succeed, with whatever Discord said about it.
+#[derive(Debug)]
 pub struct DiscordError {
+    pub status: StatusCode,
+    /// Discord's JSON error code, e.g. `10015` for an unknown webhook.
+    pub code: Option<u64>,
     pub message: String,
-    pub retry_after: f64,
+    /// Per-field problems of a `400`, nested the way Discord reports them.
+    pub errors: Option<serde_json::Value>,
+    /// Seconds to wait before trying again, only sent with a `429`.
+    pub retry_after: Option<f64>,
     pub global: bool,
 }
 
+/// The error body, every field of which is optional depending on the status.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct DiscordErrorBody {
+    code: Option<u64>,
+    message: String,
+    errors: Option<serde_json::Value>,
+    retry_after: Option<f64>,
+    global: bool,
+}
+
+/// How a failed request should be dealt with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Sent again once the rate limit is waited out.
+    RateLimited,
+    /// Sent again after a backoff, the other side may well recover.
+    Retryable,
+    /// Sending the same request again won't help.
+    Permanent,
+}
+
+impl DiscordError {
+    /// Reads Discord's error JSON, keeping a body that isn't JSON (e.g. from a proxy) as the message.
+    pub fn new(status: StatusCode, body: &str) -> Self {
+        let parsed: DiscordErrorBody = serde_json::from_str(body).unwrap_or_else(|_| DiscordErrorBody {
+            message: body.trim().to_owned(),
+            ..Default::default()
+        });
+        Self {
+            status,
+            code: parsed.code,
+            message: parsed.message,
+            errors: parsed.errors,
+            retry_after: parsed.retry_after,
+            global: parsed.global,
+        }
+    }
+
+    pub fn class(&self) -> ErrorClass {
+        match self.status {
+            StatusCode::TOO_MANY_REQUESTS => ErrorClass::RateLimited,
+            status if status.is_server_error() => ErrorClass::Retryable,
+            _ => ErrorClass::Permanent,
+        }
+    }
+
+    /// Whether the webhook itself is gone or unusable, so nothing sent to it can succeed.
+    pub fn disables_destination(&self) -> bool {
+        match self.status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => true,
+            StatusCode::NOT_FOUND => self.code != Some(UNKNOWN_MESSAGE),
+            _ => false,
+        }
+    }
+
+    /// `errors` flattened to `path: message` pairs, e.g. `embeds.0.description: Must be 4096 or fewer in length.`
+    fn field_errors(&self) -> Vec<String> {
+        fn walk(path: &str, value: &serde_json::Value, out: &mut Vec<String>) {
+            let Some(object) = value.as_object() else {
+                return;
+            };
+            if let Some(errors) = object.get("_errors").and_then(|errors| errors.as_array()) {
+                for error in errors {
+                    let message = error["message"].as_str().unwrap_or_default();
+                    out.push(format!("{path}: {message}"));
+                }
+            }
+            for (key, value) in object.iter().filter(|(key, _)| *key != "_errors") {
+                let path = if path.is_empty() { key.clone() } else { format!("{path}.{key}") };
+                walk(&path, value, out);
+            }
+        }
+        let mut out = vec![];
+        if let Some(errors) = &self.errors {
+            walk("", errors, &mut out);
+        }
+        out
+    }
+}
+
 impl Display for DiscordError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{self:?}")
+        write!(f, "{}", self.status)?;
+        if let Some(code) = self.code {
+            write!(f, " (code {code})")?;
+        }
+        if !self.message.is_empty() {
+            write!(f, ": {}", self.message)?;
+        }
+        let field_errors = self.field_errors();
+        if !field_errors.is_empty() {
+            write!(f, " [{}]", field_errors.join("; "))?;
+        }
+        Ok(())
     }
 }
 
 impl std::error::Error for DiscordError {}
+
+impl Error {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            Error::Discord(err) => err.class(),
+            Error::Http(StatusCode::TOO_MANY_REQUESTS, _) => ErrorClass::RateLimited,
+            Error::Http(status, _) if status.is_server_error() => ErrorClass::Retryable,
+            Error::Reqwest(err) if err.is_timeout() || err.is_connect() => ErrorClass::Retryable,
+            Error::Timeout(_) => ErrorClass::Retryable,
+            _ => ErrorClass::Permanent,
+        }
+    }
+
+    /// See [`DiscordError::disables_destination`], other backends lose their webhook with a `401`, `403` or `410`.
+    pub fn disables_destination(&self) -> bool {
+        match self {
+            Error::Discord(err) => err.disables_destination(),
+            Error::Http(status, _) => matches!(*status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::GONE),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn form_errors_and_unknown_webhooks_are_read_from_any_status() {
+        let body = r#"{"code": 50035, "message": "Invalid Form Body", "errors": {"embeds": {"0": {"description": {"_errors": [{"code": "BASE_TYPE_MAX_LENGTH", "message": "Must be 4096 or fewer in length."}]}}}}}"#;
+        let err = DiscordError::new(StatusCode::BAD_REQUEST, body);
+        assert_eq!(err.code, Some(50035));
+        assert_eq!(err.class(), ErrorClass::Permanent);
+        assert!(!err.disables_destination());
+        assert_eq!(err.to_string(), "400 Bad Request (code 50035): Invalid Form Body [embeds.0.description: Must be 4096 or fewer in length.]");
+
+        let err = DiscordError::new(StatusCode::NOT_FOUND, r#"{"message": "Unknown Webhook", "code": 10015}"#);
+        assert!(err.disables_destination());
+        let err = DiscordError::new(StatusCode::NOT_FOUND, r#"{"message": "Unknown Message", "code": 10008}"#);
+        assert!(!err.disables_destination());
+
+        let err = DiscordError::new(StatusCode::BAD_GATEWAY, "<html>Bad Gateway</html>");
+        assert_eq!(err.class(), ErrorClass::Retryable);
+        assert_eq!(err.message, "<html>Bad Gateway</html>");
+    }
+}
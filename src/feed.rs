@@ -0,0 +1,153 @@
+use std::{cmp::Reverse, fs::File, io::BufReader, path::{Path, PathBuf}};
+
+use atom_syndication::{Category, Content, Entry, Feed, Link, Text};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::{error::Result, notification::format_time, render::escape_markup, state::write_atomic, Notification};
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeedConfig {
+    /// Atom file every notification is added to, no feed is kept when left out.
+    pub path: Option<PathBuf>,
+    /// Entries kept, the least recently updated ones are dropped first.
+    pub max_entries: usize,
+    pub title: String,
+}
+
+impl Default for FeedConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            max_entries: 200,
+            title: "Splatoon 3 announcements".to_owned(),
+        }
+    }
+}
+
+/// Prefix of every ID in the feed, `tag:` URIs stay the same wherever the file is hosted.
+const TAG: &str = concat!("tag:", env!("CARGO_PKG_NAME"), ",2024:");
+
+/// Adds `notifications` to the feed at [`FeedConfig::path`], if there is one.
+///
+/// Entries are identified by [`Notification::key`], so a correction or cancellation
+/// replaces its original's entry and bumps `updated` instead of adding another one.
+pub fn record(config: &FeedConfig, notifications: &[Notification], now: DateTime<Utc>) -> Result<()> {
+    let Some(path) = &config.path else {
+        return Ok(());
+    };
+    let mut feed = load(config, path)?;
+    for notification in notifications {
+        let id = format!("{TAG}{}", notification.key());
+        match feed.entries.iter_mut().find(|entry| entry.id == id) {
+            // Reminders come up on every run while they're due
+            Some(_) if matches!(notification, Notification::Reminder{..}) => {},
+            Some(entry) => {
+                let published = entry.published;
+                *entry = entry_for(notification, id, now);
+                entry.published = published;
+            },
+            None => feed.entries.push(entry_for(notification, id, now)),
+        }
+    }
+    feed.entries.sort_by_key(|entry| Reverse(entry.updated));
+    feed.entries.truncate(config.max_entries);
+    feed.title = Text::plain(&config.title);
+    feed.updated = now.into();
+    write_atomic(path, &feed.write_to(Vec::new())?)?;
+    Ok(())
+}
+
+fn load(config: &FeedConfig, path: &Path) -> Result<Feed> {
+    if path.exists() {
+        return Ok(Feed::read_from(BufReader::new(File::open(path)?))?);
+    }
+    Ok(Feed {
+        id: format!("{TAG}feed"),
+        title: Text::plain(&config.title),
+        ..Default::default()
+    })
+}
+
+fn entry_for(notification: &Notification, id: String, now: DateTime<Utc>) -> Entry {
+    let facts: String = notification.facts().into_iter()
+        .chain([("Starts", format_time(notification.start())), ("Ends", format_time(notification.end()))])
+        .map(|(name, value)| format!("<li><b>{name}</b>: {}</li>", escape_markup(&value)))
+        .collect()
+    ;
+    Entry {
+        id,
+        title: Text::plain(notification.to_string()),
+        updated: now.into(),
+        published: Some(now.into()),
+        summary: Some(Text::plain(notification.title())),
+        content: Some(Content {
+            value: Some(format!("<p>{}</p><ul>{facts}</ul>", escape_markup(notification.title()))),
+            content_type: Some("html".to_owned()),
+            ..Default::default()
+        }),
+        categories: vec![Category { term: notification.kind().to_string(), ..Default::default() }],
+        links: notification.image().into_iter().map(|image| Link {
+            href: image.to_owned(),
+            rel: "enclosure".to_owned(),
+            mime_type: Some(mime_type(image).to_owned()),
+            ..Default::default()
+        }).collect(),
+        ..Default::default()
+    }
+}
+
+/// Guessed from the extension, splatoon3.ink images are PNGs.
+fn mime_type(url: &str) -> &'static str {
+    let path = url.split(['?', '#']).next().unwrap_or_default().to_ascii_lowercase();
+    match path.rsplit_once('.').map(|(_, ext)| ext) {
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        _ => "image/png",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{notification::FieldChange, rotation::Rotation, schedule_data::RotationData};
+
+    #[test]
+    fn corrections_update_the_original_entry_and_history_is_capped() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = FeedConfig { path: Some(dir.path().join("feed.xml")), max_entries: 2, ..Default::default() };
+        let data: RotationData = serde_json::from_str(include_str!("../tests/fixtures/schedules_new.json")).unwrap();
+        let rotations: Vec<_> = data.data.coopGroupingSchedule.regularSchedules.nodes.iter().filter_map(Rotation::notification).collect();
+        let first = rotations[0].clone();
+        let t0 = Utc::now() - chrono::TimeDelta::hours(1);
+
+        record(&config, std::slice::from_ref(&first), t0).unwrap();
+        let correction = Notification::Correction {
+            original: Box::new(first.clone()),
+            changes: vec![FieldChange { field: "Stage", old: "a".to_owned(), new: "b".to_owned() }],
+            image: "https://example.org/stage.png".to_owned(),
+        };
+        let t1 = Utc::now();
+        record(&config, &[correction], t1).unwrap();
+
+        let feed = Feed::read_from(BufReader::new(File::open(config.path.as_ref().unwrap()).unwrap())).unwrap();
+        assert_eq!(feed.entries.len(), 1);
+        let entry = &feed.entries[0];
+        assert_eq!(entry.id, format!("{TAG}{}", first.key()));
+        assert_eq!(entry.updated.timestamp(), t1.timestamp());
+        assert_eq!(entry.published.map(|published| published.timestamp()), Some(t0.timestamp()));
+        assert_eq!(entry.links[0].rel, "enclosure");
+        assert_eq!(entry.links[0].mime_type.as_deref(), Some("image/png"));
+
+        let older = Notification::BigRun { start: t0, end: t1, king: "Cohozuna".to_owned(), stage: ("Stage".to_owned(), "https://example.org/a.jpg".to_owned()) };
+        let newer = Notification::BigRun { start: t1, end: t1, king: "Cohozuna".to_owned(), stage: ("Stage".to_owned(), "https://example.org/b.jpg".to_owned()) };
+        record(&config, &[older], t1 + chrono::TimeDelta::minutes(1)).unwrap();
+        record(&config, std::slice::from_ref(&newer), t1 + chrono::TimeDelta::minutes(2)).unwrap();
+        let feed = Feed::read_from(BufReader::new(File::open(config.path.as_ref().unwrap()).unwrap())).unwrap();
+        assert_eq!(feed.entries.len(), 2);
+        assert_eq!(feed.entries[0].id, format!("{TAG}{}", newer.key()));
+        assert!(feed.entries.iter().all(|entry| entry.id != format!("{TAG}{}", first.key())));
+    }
+}
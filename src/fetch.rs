@@ -0,0 +1,171 @@
+use std::{future::Future, time::Duration};
+
+use reqwest::{header::{HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED}, Client, StatusCode};
+use serde::{de, Deserialize, Serialize};
+
+use crate::{config::SourcesConfig, error::{Error, ErrorClass, Result}, schema::{self, Report}, send::check_status, state::StateStore};
+
+/// Names the previous documents are committed under in the [`StateStore`].
+pub const SCHEDULES_CACHE: &str = "Schedules Json.json";
+pub const FESTIVALS_CACHE: &str = "Splatfest Json.json";
+
+/// `ETag` and `Last-Modified` of a response, sent back so an unchanged document isn't downloaded again.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+/// The answer to a conditional request.
+#[derive(Debug)]
+pub enum Conditional {
+    Modified { body: String, validators: Validators },
+    /// `304 Not Modified`, the document still matches the validators sent.
+    NotModified,
+}
+
+/// Downloads the raw splatoon3.ink documents.
+pub trait Fetcher {
+    /// The body served at `url`.
+    fn fetch(&self, url: &str) -> impl Future<Output = Result<String>>;
+
+    /// Like [`fetch`](Self::fetch), but sends `validators` along so an unchanged document comes back as [`Conditional::NotModified`].
+    ///
+    /// Fetchers that can't make conditional requests always download the whole document.
+    fn fetch_if_modified(&self, url: &str, _validators: &Validators) -> impl Future<Output = Result<Conditional>> {
+        async move {
+            Ok(Conditional::Modified { body: self.fetch(url).await?, validators: Validators::default() })
+        }
+    }
+}
+
+impl Fetcher for Client {
+    async fn fetch(&self, url: &str) -> Result<String> {
+        Ok(check_status(self.get(url).send().await?).await?.text().await?)
+    }
+
+    async fn fetch_if_modified(&self, url: &str, validators: &Validators) -> Result<Conditional> {
+        let mut request = self.get(url);
+        if let Some(etag) = &validators.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Conditional::NotModified);
+        }
+        let response = check_status(response).await?;
+        let header = |name| response.headers().get(name).and_then(|value: &HeaderValue| value.to_str().ok()).map(str::to_owned);
+        let validators = Validators { etag: header(ETAG), last_modified: header(LAST_MODIFIED) };
+        Ok(Conditional::Modified { body: response.text().await?, validators })
+    }
+}
+
+/// Fetches and parses `url` without touching the cache used for diffing, logging the nodes that had to be left out.
+pub async fn get_current<T: de::DeserializeOwned, F: Fetcher>(fetcher: &F, url: &str) -> Result<T> {
+    let (data, report) = schema::parse(&fetcher.fetch(url).await?)?;
+    report.log(url);
+    Ok(data)
+}
+
+/// The copy last committed under `name` in `store`, if there is one.
+pub fn get_cached<T: de::DeserializeOwned, St: StateStore>(store: &St, name: &str) -> Result<Option<T>> {
+    store.cached(name)?.map(|json| serde_json::from_str(&json)).transpose().map_err(Error::from)
+}
+
+/// What [`get_data`] got for a source, and where it came from.
+#[derive(Debug)]
+pub enum Fetched<T> {
+    /// splatoon3.ink served the document.
+    Live {
+        fresh: T,
+        /// The previously committed copy, `fresh` again on the first run.
+        previous: T,
+        /// To send along next time, once `fresh` is committed.
+        validators: Validators,
+        /// Nodes of `fresh` that were left out and fields that weren't recognized.
+        report: Report,
+    },
+    /// splatoon3.ink says the committed copy is still current.
+    Cached,
+    /// splatoon3.ink couldn't be reached or served something unreadable, the committed copy stands in for it.
+    Stale(Error),
+}
+
+/// Fetches `url` along with the copy last committed under `name` in `store`.
+///
+/// `validators` are those of the committed copy, sent along so an unchanged document comes back as [`Fetched::Cached`].
+/// Timeouts and server errors are retried as set in `sources`, after that the committed copy is used as [`Fetched::Stale`].
+/// Nothing is written, the caller commits the fresh data once it was delivered.
+pub async fn get_data<T, F, St>(fetcher: &F, store: &St, sources: &SourcesConfig, url: &str, name: &str, validators: &Validators) -> Result<Fetched<T>>
+    where
+        F: Fetcher,
+        St: StateStore,
+        T: de::DeserializeOwned + Clone,
+    {
+    let cached = store.cached(name)?;
+    // Without a committed copy there is nothing a 304 could point at
+    let validators = if cached.is_some() { validators.clone() } else { Validators::default() };
+    let fetched = fetch_with_retries(fetcher, sources, url, &validators).await.and_then(|conditional| match conditional {
+        Conditional::NotModified => Ok(None),
+        Conditional::Modified { body, validators } => Ok(Some((schema::parse::<T>(&body)?, validators))),
+    });
+    match (fetched, cached) {
+        (Ok(None), _) => Ok(Fetched::Cached),
+        (Ok(Some(((fresh, report), validators))), cached) => {
+            let previous = match cached {
+                Some(json) => serde_json::from_str(&json)?,
+                None => fresh.clone(),
+            };
+            Ok(Fetched::Live { fresh, previous, validators, report })
+        },
+        (Err(err), Some(_)) => Ok(Fetched::Stale(err)),
+        (Err(err), None) => Err(err),
+    }
+}
+
+/// Tries `url` up to [`SourcesConfig::retries`] more times while it times out or fails in a way that may go away.
+async fn fetch_with_retries<F: Fetcher>(fetcher: &F, sources: &SourcesConfig, url: &str, validators: &Validators) -> Result<Conditional> {
+    let timeout = Duration::from_secs(sources.timeout_secs);
+    let mut attempt = 0;
+    loop {
+        let result = match tokio::time::timeout(timeout, fetcher.fetch_if_modified(url, validators)).await {
+            Ok(result) => result,
+            Err(_) => Err(Error::Timeout(timeout)),
+        };
+        match result {
+            Err(err) if attempt < sources.retries && err.class() != ErrorClass::Permanent => {
+                attempt += 1;
+                tokio::time::sleep(jittered_backoff(sources.backoff_ms, attempt)).await;
+            },
+            result => break result,
+        }
+    }
+}
+
+/// `backoff_ms` doubled for every retry after the first, anywhere from half to one and a half times that.
+///
+/// The jitter keeps instances that lost splatoon3.ink at the same time from all coming back at once.
+fn jittered_backoff(backoff_ms: u64, attempt: u32) -> Duration {
+    let backoff = backoff_ms.saturating_mul(1 << (attempt - 1).min(16));
+    Duration::from_millis(backoff / 2 + fastrand::u64(0..=backoff))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_within_its_jitter() {
+        for attempt in 1..=4 {
+            let base = 100 << (attempt - 1);
+            for _ in 0..100 {
+                let wait = jittered_backoff(100, attempt).as_millis() as u64;
+                assert!((base / 2..=base * 3 / 2).contains(&wait), "{wait}ms on attempt {attempt}");
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{diff::Identity, schedule_data::data, splatfest_data::SplatfestData};
+
+/// A scheduled event as it appeared in a splatoon3.ink document, kept by stores that keep history.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    /// What was scheduled, e.g. `salmon_run`, `pvp_x` or `splatfest`.
+    pub kind: &'static str,
+    /// Stays the same every time the same event is fetched again.
+    pub identity: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// The node as splatoon3.ink served it.
+    pub data: Value,
+}
+
+/// What a `history` query asks a store for, newest first.
+#[derive(Debug, Clone)]
+pub enum HistoryQuery {
+    Observations { kind: Option<String>, limit: usize },
+    Deliveries { destination: Option<String>, failed_only: bool, limit: usize },
+}
+
+fn observation<T: Serialize>(kind: &'static str, identity: String, start: &DateTime<Local>, end: &DateTime<Local>, node: &T) -> Observation {
+    Observation {
+        kind,
+        identity,
+        start: start.to_utc(),
+        end: end.to_utc(),
+        data: serde_json::to_value(node).unwrap_or_default(),
+    }
+}
+
+/// Every Salmon Run and PvP rotation and every Challenge in a schedules document.
+pub fn schedule_observations(data: &data) -> Vec<Observation> {
+    let coop = &data.coopGroupingSchedule;
+    // Salmon Run rotations are kept apart by their stage as well as their time slot, so a revised stage is observed as its own event
+    let salmon = |start: &DateTime<Local>, stage: &str| format!("{}:{stage}", start.timestamp());
+    let mut observations = vec![];
+    observations.extend(coop.regularSchedules.nodes.iter().map(|node|
+        observation("salmon_run", salmon(&node.startTime, &node.setting.coopStage.id), &node.startTime, &node.endTime, node)
+    ));
+    observations.extend(coop.bigRunSchedules.nodes.iter().map(|node|
+        observation("big_run", salmon(&node.startTime, &node.setting.coopStage.id), &node.startTime, &node.endTime, node)
+    ));
+    observations.extend(coop.teamContestSchedules.nodes.iter().map(|node|
+        observation("eggstra_work", salmon(&node.startTime, &node.setting.coopStage.id), &node.startTime, &node.endTime, node)
+    ));
+    observations.extend(data.regularSchedules.nodes.iter().map(|node|
+        observation("pvp_regular", node.startTime.timestamp().to_string(), &node.startTime, &node.endTime, node)
+    ));
+    observations.extend(data.bankaraSchedules.nodes.iter().map(|node|
+        observation("pvp_anarchy", node.startTime.timestamp().to_string(), &node.startTime, &node.endTime, node)
+    ));
+    observations.extend(data.xSchedules.nodes.iter().map(|node|
+        observation("pvp_x", node.startTime.timestamp().to_string(), &node.startTime, &node.endTime, node)
+    ));
+    observations.extend(data.eventSchedules.nodes.iter().filter_map(|node| {
+        let (first, last) = (node.timePeriods.first()?, node.timePeriods.last()?);
+        let (id, _) = node.identity();
+        Some(observation("challenge", format!("{id}:{}", first.startTime.timestamp()), &first.startTime, &last.endTime, node))
+    }));
+    observations
+}
+
+/// Every Splatfest of any region in a festivals document.
+pub fn splatfest_observations(data: &SplatfestData) -> Vec<Observation> {
+    data.fests().iter().map(|fest| {
+        let mut observation = observation("splatfest", fest.identity(), &fest.fest.startTime, &fest.fest.endTime, &fest.fest);
+        if let Value::Object(fields) = &mut observation.data {
+            fields.insert("regions".to_owned(), serde_json::to_value(&fest.regions).unwrap_or_default());
+        }
+        observation
+    })
+    .collect()
+}
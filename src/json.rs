@@ -0,0 +1,61 @@
+use reqwest::Client;
+use serde_json::{json, Value};
+
+use crate::{
+    config::{Destination, KindStyle},
+    error::{Error, Result},
+    notification::Notification,
+    render::Renderer,
+    send::{check_status, Sender},
+};
+
+/// The notification's fields as a flat JSON object, for anything that isn't a chat service.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonRenderer;
+
+impl Renderer for JsonRenderer {
+    type Output = Value;
+
+    fn render(&self, notification: &Notification, style: &KindStyle) -> Value {
+        let facts: Vec<_> = notification.facts().into_iter().map(|(name, value)| json!({ "name": name, "value": value })).collect();
+        json!({
+            "kind": notification.kind(),
+            "key": notification.key(),
+            "title": notification.title(),
+            "summary": notification.to_string(),
+            "start": notification.start().to_rfc3339(),
+            "end": notification.end().to_rfc3339(),
+            "color": notification.color(),
+            "image": notification.image(),
+            "facts": facts,
+            "ping": notification.ping(style),
+        })
+    }
+
+    fn render_alert(&self, text: &str) -> Value {
+        json!({ "kind": "alert", "text": text })
+    }
+}
+
+/// A plain `POST` of the JSON body, without any message IDs to follow up on.
+#[derive(Debug, Clone, Default)]
+pub struct JsonWebhook {
+    pub client: Client,
+}
+
+impl Sender<Value> for JsonWebhook {
+    async fn post(&self, destination: &Destination, message: &Value) -> Result<Option<String>> {
+        let response = self.client.post(&destination.webhook_url).json(message).send().await?;
+        check_status(response).await?;
+        Ok(None)
+    }
+
+    /// Posts the new version, the receiver can match it up by `key`.
+    async fn edit(&self, destination: &Destination, _message_id: &str, message: &Value) -> Result<()> {
+        self.post(destination, message).await.map(|_| ())
+    }
+
+    async fn delete(&self, destination: &Destination, _message_id: &str) -> Result<()> {
+        Err(Error::Config(format!("destination `{}` is a JSON webhook, which can't delete messages", destination.name)))
+    }
+}
@@ -0,0 +1,435 @@
+//! Announces Splatoon 3 events from [splatoon3.ink](https://splatoon3.ink) to Discord, Slack, Matrix or plain JSON webhooks.
+//!
+//! A run goes through these steps, each with its own extension point:
+//!
+//! 1. A [`Fetcher`] downloads the schedules and Splatfests, [`fetch::get_data`] parses them with [`schema::parse`] and pairs them with the previous copy.
+//! 2. [`diff`] compares both copies by [`Identity`](diff::Identity) and the changes become [`Notification`]s.
+//! 3. A [`Sink`] delivers each notification to every matching [destination](config::Destination).
+//!    The built-in ones pair a [`Renderer`](render::Renderer), turning it into a message, with a [`Sender`](send::Sender) for that message.
+//! 4. A [`StateStore`] keeps the message IDs needed for follow-ups, and the [`feed`] keeps an Atom copy of every notification.
+//!
+//! [`Bot`] wires these together, [`WebhookBot`] is the setup the binary uses.
+//! [`Bot::calendar`] turns the same data into an iCalendar feed instead.
+
+extern crate serde;
+extern crate chrono;
+
+pub mod calendar;
+pub mod config;
+pub mod daemon;
+pub mod diff;
+pub mod error;
+pub mod feed;
+pub mod fetch;
+pub mod history;
+pub mod json;
+pub mod matrix;
+pub mod notification;
+pub mod pvp;
+pub mod ratelimit;
+pub mod reminder;
+pub mod render;
+pub mod rotation;
+pub mod schedule_data;
+pub mod schema;
+pub mod send;
+pub mod sink;
+pub mod slack;
+pub mod splatfest;
+pub mod splatfest_data;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod state;
+pub mod status;
+pub mod watch;
+
+use std::{collections::{BTreeMap, HashSet}, fmt::Display, sync::Arc};
+
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+use reqwest::Client;
+use tokio::join;
+
+use config::Config;
+use diff::Change;
+use error::{Error, ErrorClass, Result};
+use fetch::{get_cached, get_current, get_data, Fetched, Fetcher, Validators, FESTIVALS_CACHE, SCHEDULES_CACHE};
+use history::Observation;
+use rotation::{rotation_notifications, Rotation};
+use schedule_data::{data, RotationData};
+use schema::Report;
+use send::{send_notifications, Outcome};
+use sink::{Sink, Sinks};
+use splatfest::{has_results, splatfest_notification, splatfest_results_notification};
+use splatfest_data::SplatfestData;
+use state::{State, StateStore, Store};
+use status::{Metrics, Timed};
+
+pub use notification::{Notification, NotificationKind};
+
+/// Everything a run needs.
+#[derive(Debug, Clone)]
+pub struct Bot<F, K, St> {
+    pub config: Config,
+    pub fetcher: F,
+    pub sink: K,
+    pub store: St,
+    /// Shared with the [status server](status::serve).
+    pub metrics: Arc<Metrics>,
+}
+
+/// Source labels used in [`Metrics`].
+pub const SOURCES: &[&str] = &["schedules", "festivals"];
+
+/// A splatoon3.ink document, by its label in [`SOURCES`] and the name its copy is committed under.
+#[derive(Debug, Clone, Copy)]
+struct Source {
+    label: &'static str,
+    cache: &'static str,
+}
+
+const SCHEDULES: Source = Source { label: SOURCES[0], cache: SCHEDULES_CACHE };
+const FESTIVALS: Source = Source { label: SOURCES[1], cache: FESTIVALS_CACHE };
+
+impl Source {
+    /// The source notifications of `kind` are diffed from.
+    fn of(kind: NotificationKind) -> Self {
+        if kind == NotificationKind::Splatfest { FESTIVALS } else { SCHEDULES }
+    }
+}
+
+/// Fetches with the HTTP client and sends through each destination's [`Backend`](sink::Backend), keeping state in the working directory.
+///
+/// The store is shared with the [status server](status::serve), which reads `/state` from it.
+pub type WebhookBot = Bot<Client, Sinks, Arc<Store>>;
+
+impl<F, K, St> Bot<F, K, St>
+where
+    F: Fetcher,
+    K: Sink,
+    St: StateStore,
+{
+    /// Fetches both sources once and sends whatever is new to every matching destination.
+    ///
+    /// A source that can't be fetched is left out of the run and reported in [`Summary::fetch_errors`], the other one is still sent.
+    pub async fn run_once(&self) -> Result<Summary> {
+        let config = &self.config;
+        let mut state = self.store.load()?;
+        // Both sources are sent together so every webhook's queue is in start order across them
+        let (schedules, splatfests) = join!(self.get_schedule_notifications(&state), self.get_splatfest_notifications(&state));
+        let mut fetch_errors = vec![];
+        let batches: Vec<_> = [(SCHEDULES, schedules), (FESTIVALS, splatfests)].into_iter()
+            .filter_map(|(source, res)| res.map_err(|err| fetch_errors.push((source.label, err))).ok())
+            .collect()
+        ;
+        let mut summary = Summary {
+            next_reminder: batches.iter().filter_map(|batch| batch.next_reminder).min(),
+            stale: batches.iter().filter(|batch| batch.stale.is_some()).map(|batch| batch.source.label).collect(),
+            ..Default::default()
+        };
+        self.track_staleness(&mut state, &batches).await;
+        let observations: Vec<_> = batches.iter().flat_map(|batch| batch.observations.iter().cloned()).collect();
+        // History is a side channel like the feed
+        if let Err(err) = self.store.record_observations(&observations) {
+            eprintln!("History Err: {err}");
+        }
+        let (notifications, caches): (Vec<_>, Vec<_>) = batches.into_iter().map(|batch| (batch.notifications, batch.cache)).unzip();
+        let notifications: Vec<_> = notifications.into_iter().flatten().collect();
+        for destination in &config.destinations {
+            if let Some(reason) = state.disabled_reason(destination) {
+                eprintln!("Skipping disabled destination `{}` ({reason}), give it a new webhook_url to enable it again", destination.name);
+            }
+        }
+        let deliveries = send_notifications(&self.sink, config, &state, &notifications).await;
+        self.sink.end_run();
+        self.metrics.record_deliveries(&deliveries);
+        if let Err(err) = self.store.record_deliveries(&deliveries) {
+            eprintln!("History Err: {err}");
+        }
+        // Failures that may go away are retried by diffing their source against the same cache next run
+        let mut held = HashSet::new();
+        let mut failing = HashSet::new();
+        for delivery in deliveries {
+            let (sent, failed) = summary.destinations.entry(delivery.destination.to_owned()).or_default();
+            if delivery.result.is_ok() {
+                *sent += 1;
+                state.record_delivery(delivery.destination, delivery.kind, &delivery.variant, &delivery.key);
+            }
+            match delivery.result {
+                // Nothing follows up on a reminder, it only has to be sent once
+                Ok(_) if delivery.reminder => state.record_reminder(delivery.destination, delivery.key, delivery.until),
+                Ok(Outcome::Posted(message_id)) => {
+                    // Follow-ups keep pointing at the original announcement
+                    if let Some(message_id) = message_id.filter(|_| state.message_id(delivery.destination, &delivery.key).is_none()) {
+                        state.record_message(delivery.destination, delivery.key, message_id, delivery.until);
+                    }
+                },
+                Ok(Outcome::Edited) => {},
+                Ok(Outcome::Deleted) => state.forget_message(delivery.destination, &delivery.key),
+                Err(err) => {
+                    *failed += 1;
+                    if err.class() != ErrorClass::Permanent {
+                        failing.insert(delivery.destination);
+                        if state.held_runs(delivery.destination) < config.sending.max_held_runs {
+                            held.insert(Source::of(delivery.kind).cache);
+                        }
+                    }
+                    let destination = config.destinations.iter().find(|destination| destination.name == delivery.destination);
+                    if let Some(destination) = destination.filter(|_| err.disables_destination()) {
+                        state.disable(destination, err.to_string());
+                    }
+                },
+            }
+        }
+        for destination in &config.destinations {
+            let held_runs = state.record_held_run(&destination.name, failing.contains(destination.name.as_str()));
+            if held_runs == config.sending.max_held_runs + 1 {
+                eprintln!("`{}` kept failing for {held_runs} runs, the caches are no longer held back for it", destination.name);
+            }
+        }
+        // Caches go first, a crash before the state is saved only leaves some delivery records behind
+        for commit in caches.iter().flatten() {
+            if held.contains(commit.source.cache) {
+                eprintln!("Keeping the previous {} copy, failed notifications are sent again next run", commit.source.label);
+                continue;
+            }
+            self.store.commit_cache(commit.source.cache, &commit.json)?;
+            state.sources.entry(commit.source.cache.to_owned()).or_default().validators = commit.validators.clone();
+            state.clear_deliveries(|kind| Source::of(kind).cache == commit.source.cache);
+        }
+        state.forget_ended(Utc::now());
+        self.store.save(&state)?;
+        // The feed is a side channel, it doesn't get to fail a run that already delivered
+        if let Err(err) = feed::record(&config.feed, &notifications, Utc::now()) {
+            eprintln!("Feed Err: {err}");
+        }
+        summary.fetch_errors = fetch_errors;
+        Ok(summary)
+    }
+
+    async fn get_schedule_notifications(&self, state: &State) -> Result<Batch> {
+        let config = &self.config;
+        let validators = state.sources.get(SCHEDULES.cache).map(|source| source.validators.clone()).unwrap_or_default();
+        let events = |store: &St| Ok(get_cached::<RotationData, _>(store, SCHEDULES.cache)?.map(|data| schedule_events(&data.data)));
+        let (internet_data, file_data, validators) =
+            match get_data::<RotationData,_,_>(&self.timed(SCHEDULES.label), &self.store, &config.sources, &config.sources.schedules_url, SCHEDULES.cache, &validators).await? {
+                Fetched::Live { mut fresh, previous, validators, report } => {
+                    report.log(SCHEDULES.label);
+                    keep_skipped_schedules(&report, &previous.data, &mut fresh.data);
+                    (fresh, previous, validators)
+                },
+                Fetched::Cached => return self.unchanged(SCHEDULES, None, events),
+                Fetched::Stale(err) => return self.unchanged(SCHEDULES, Some(err), events),
+            }
+        ;
+        let (internet_schedule, file_schedule) = (&internet_data.data.coopGroupingSchedule, &file_data.data.coopGroupingSchedule);
+        let regular_notifications = rotation_notifications(config, &file_schedule.regularSchedules.nodes, &internet_schedule.regularSchedules.nodes);
+        let big_run_notifications = rotation_notifications(config, &file_schedule.bigRunSchedules.nodes, &internet_schedule.bigRunSchedules.nodes);
+        let eggstra_work_notifications = rotation_notifications(config, &file_schedule.teamContestSchedules.nodes, &internet_schedule.teamContestSchedules.nodes);
+        let pvp_notifications = pvp::rotation_notifications(&config.pvp, &file_data.data, &internet_data.data);
+        let challenge_notifications = pvp::challenge_notifications(&config.pvp, &file_data.data, &internet_data.data);
+        let watched_notifications = watch::rotation_notifications(&config.watchlist, &file_schedule.regularSchedules.nodes, &internet_schedule.regularSchedules.nodes);
+        let notifications = regular_notifications.chain(big_run_notifications).chain(eggstra_work_notifications)
+            .chain(watched_notifications)
+            .chain(pvp_notifications)
+            .chain(challenge_notifications)
+            .collect()
+        ;
+        let cache = Commit { source: SCHEDULES, json: serde_json::to_string(&internet_data)?, validators };
+        let mut batch = Batch::with_reminders(config, SCHEDULES, notifications, &schedule_events(&internet_data.data), Some(cache));
+        batch.observations = history::schedule_observations(&internet_data.data);
+        Ok(batch)
+    }
+
+    async fn get_splatfest_notifications(&self, state: &State) -> Result<Batch> {
+        let sources = &self.config.sources;
+        let validators = state.sources.get(FESTIVALS.cache).map(|source| source.validators.clone()).unwrap_or_default();
+        let events = |store: &St| Ok(get_cached::<SplatfestData, _>(store, FESTIVALS.cache)?.map(|data| splatfest_events(&data)));
+        let (internet_data, file_data, validators) =
+            match get_data::<SplatfestData,_,_>(&self.timed(FESTIVALS.label), &self.store, sources, &sources.festivals_url, FESTIVALS.cache, &validators).await? {
+                Fetched::Live { mut fresh, previous, validators, report } => {
+                    report.log(FESTIVALS.label);
+                    keep_skipped_splatfests(&report, &previous, &mut fresh);
+                    (fresh, previous, validators)
+                },
+                Fetched::Cached => return self.unchanged(FESTIVALS, None, events),
+                Fetched::Stale(err) => return self.unchanged(FESTIVALS, Some(err), events),
+            }
+        ;
+        let internet_fests = internet_data.fests();
+        let splatfest_notifications =
+            diff::diff(&file_data.fests(), &internet_fests).into_iter()
+            .filter_map(|change| match change {
+                Change::Added(fest) => Some(splatfest_notification(&fest)),
+                Change::Changed{old, new} => (!has_results(&old) && has_results(&new)).then(|| splatfest_results_notification(&new)).flatten(),
+                Change::Removed(_) => None,
+            })
+        ;
+        let notifications = splatfest_notifications.collect();
+        let cache = Commit { source: FESTIVALS, json: serde_json::to_string(&internet_data)?, validators };
+        let mut batch = Batch::with_reminders(&self.config, FESTIVALS, notifications, &splatfest_events(&internet_data), Some(cache));
+        batch.observations = history::splatfest_observations(&internet_data);
+        Ok(batch)
+    }
+
+    /// A source splatoon3.ink says is unchanged, or that is `stale` because it failed, has nothing to diff, only reminders can be due.
+    ///
+    /// `events` reads them from the committed copy, which is left alone when no reminders are configured.
+    fn unchanged(&self, source: Source, stale: Option<Error>, events: impl FnOnce(&St) -> Result<Option<Vec<Notification>>>) -> Result<Batch> {
+        let events = if self.config.reminders.offsets_minutes.is_empty() {
+            vec![]
+        } else {
+            events(&self.store)?.unwrap_or_default()
+        };
+        let mut batch = Batch::with_reminders(&self.config, source, vec![], &events, None);
+        batch.stale = stale;
+        Ok(batch)
+    }
+
+    /// Notes which sources only had their committed copy and alerts once one went without live data for `[alerts]` `stale_after_minutes`.
+    async fn track_staleness(&self, state: &mut State, batches: &[Batch]) {
+        let alerts = &self.config.alerts;
+        let now = Utc::now();
+        for batch in batches {
+            let source = state.sources.entry(batch.source.cache.to_owned()).or_default();
+            let Some(err) = &batch.stale else {
+                source.stale_since = None;
+                source.alerted = false;
+                continue;
+            };
+            let since = *source.stale_since.get_or_insert(now);
+            eprintln!("Using the committed copy of {}, splatoon3.ink has been failing since {since}: {err}", batch.source.label);
+            let overdue = now - since >= TimeDelta::minutes(alerts.stale_after_minutes as i64);
+            let Some(destination) = alerts.destination().filter(|_| overdue && !source.alerted) else {
+                continue;
+            };
+            let text = format!(
+                "No live {} data from splatoon3.ink since {}, announcements are based on the last copy fetched. Last error: {err}",
+                batch.source.label,
+                since.to_rfc3339_opts(SecondsFormat::Secs, true),
+            );
+            match self.sink.alert(&destination, &text).await {
+                Ok(()) => source.alerted = true,
+                Err(err) => eprintln!("Alert Err: {err}"),
+            }
+        }
+    }
+
+    fn timed(&self, source: &'static str) -> Timed<'_, F> {
+        Timed { fetcher: &self.fetcher, metrics: &self.metrics, source }
+    }
+
+    /// The currently scheduled Salmon Run events and Splatfests as an iCalendar document.
+    ///
+    /// Fetches both sources without touching the caches, so the next [`run_once`](Self::run_once) still sees what's new.
+    pub async fn calendar(&self) -> Result<String> {
+        let (schedules, splatfests) = join!(
+            get_current::<RotationData, _>(&self.fetcher, &self.config.sources.schedules_url),
+            get_current::<SplatfestData, _>(&self.fetcher, &self.config.sources.festivals_url),
+        );
+        let events: Vec<_> = schedule_events(&schedules?.data).into_iter().chain(splatfest_events(&splatfests?)).collect();
+        Ok(calendar::calendar(&self.config.calendar, &events, Utc::now()))
+    }
+}
+
+/// Every scheduled Salmon Run rotation worth an announcement of its own.
+fn schedule_events(data: &data) -> Vec<Notification> {
+    let schedule = &data.coopGroupingSchedule;
+    schedule.regularSchedules.nodes.iter().filter_map(Rotation::notification)
+        .chain(schedule.bigRunSchedules.nodes.iter().filter_map(Rotation::notification))
+        .chain(schedule.teamContestSchedules.nodes.iter().filter_map(Rotation::notification))
+        .collect()
+}
+
+fn splatfest_events(data: &SplatfestData) -> Vec<Notification> {
+    data.fests().iter().map(splatfest_notification).collect()
+}
+
+/// Carries what `previous` had of each list a node was skipped from over into `fresh`, see [`Report::keep_skipped`].
+fn keep_skipped_schedules(report: &Report, previous: &data, fresh: &mut data) {
+    let now = Utc::now();
+    let (old, new) = (&previous.coopGroupingSchedule, &mut fresh.coopGroupingSchedule);
+    report.keep_skipped("data.coopGroupingSchedule.regularSchedules.nodes", &old.regularSchedules.nodes, &mut new.regularSchedules.nodes, |node| node.endTime <= now);
+    report.keep_skipped("data.coopGroupingSchedule.bigRunSchedules.nodes", &old.bigRunSchedules.nodes, &mut new.bigRunSchedules.nodes, |node| node.endTime <= now);
+    report.keep_skipped("data.coopGroupingSchedule.teamContestSchedules.nodes", &old.teamContestSchedules.nodes, &mut new.teamContestSchedules.nodes, |node| node.endTime <= now);
+    report.keep_skipped("data.regularSchedules.nodes", &previous.regularSchedules.nodes, &mut fresh.regularSchedules.nodes, |node| node.endTime <= now);
+    report.keep_skipped("data.bankaraSchedules.nodes", &previous.bankaraSchedules.nodes, &mut fresh.bankaraSchedules.nodes, |node| node.endTime <= now);
+    report.keep_skipped("data.xSchedules.nodes", &previous.xSchedules.nodes, &mut fresh.xSchedules.nodes, |node| node.endTime <= now);
+    report.keep_skipped("data.eventSchedules.nodes", &previous.eventSchedules.nodes, &mut fresh.eventSchedules.nodes,
+        |node| node.timePeriods.last().is_none_or(|period| period.endTime <= now));
+}
+
+/// Carries what `previous` had of each region a Splatfest was skipped from over into `fresh`, see [`Report::keep_skipped`].
+fn keep_skipped_splatfests(report: &Report, previous: &SplatfestData, fresh: &mut SplatfestData) {
+    let now = Utc::now();
+    for (name, old, new) in [
+        ("US", &previous.US, &mut fresh.US),
+        ("EU", &previous.EU, &mut fresh.EU),
+        ("JP", &previous.JP, &mut fresh.JP),
+        ("AP", &previous.AP, &mut fresh.AP),
+    ] {
+        report.keep_skipped(&format!("{name}.data.festRecords.nodes"), &old.data.festRecords.nodes, &mut new.data.festRecords.nodes, |fest| fest.endTime <= now);
+    }
+}
+
+/// Sent and failed counts per destination name.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub destinations: BTreeMap<String, (usize, usize)>,
+    /// When the next reminder becomes due, if any are scheduled.
+    pub next_reminder: Option<DateTime<Utc>>,
+    /// Sources that only had their committed copy, see [`Fetched::Stale`].
+    pub stale: Vec<&'static str>,
+    /// Sources left out of the run, along with why.
+    pub fetch_errors: Vec<(&'static str, Error)>,
+}
+
+/// What a single source produced in a run.
+#[derive(Debug)]
+struct Batch {
+    source: Source,
+    notifications: Vec<Notification>,
+    next_reminder: Option<DateTime<Utc>>,
+    /// What to commit once its notifications are out, nothing when splatoon3.ink had no changes.
+    cache: Option<Commit>,
+    observations: Vec<Observation>,
+    /// Why the committed copy stood in for splatoon3.ink, if it did.
+    stale: Option<Error>,
+}
+
+/// A fetched document along with the source it is committed for.
+#[derive(Debug)]
+struct Commit {
+    source: Source,
+    json: String,
+    validators: Validators,
+}
+
+impl Batch {
+    /// Adds the reminders that are due for the currently scheduled `events`.
+    fn with_reminders(config: &Config, source: Source, mut notifications: Vec<Notification>, events: &[Notification], cache: Option<Commit>) -> Self {
+        let now = Utc::now();
+        notifications.extend(reminder::due(&config.reminders, events, now));
+        Self {
+            source,
+            notifications,
+            next_reminder: reminder::next(&config.reminders, events, now),
+            cache,
+            observations: vec![],
+            stale: None,
+        }
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (sent, failed) = self.destinations.values().fold((0, 0), |(s, f), (sent, failed)| (s + sent, f + failed));
+        writeln!(f, "Notifs sent: {sent} | Notifs failed: {failed}")?;
+        for (destination, (sent, failed)) in &self.destinations {
+            writeln!(f, "  {destination}: sent {sent} | failed {failed}")?;
+        }
+        if !self.stale.is_empty() {
+            writeln!(f, "Stale sources: {}", self.stale.join(", "))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use std::{collections::HashMap, sync::{atomic::{AtomicU64, Ordering}, Arc, Mutex}, time::{SystemTime, UNIX_EPOCH}};
+
+use reqwest::{Client, Response, Url};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    config::{Destination, KindStyle},
+    error::{Error, ErrorClass, Result},
+    notification::{format_time, Notification},
+    render::{escape_markup, Renderer},
+    send::{check_status, Sender},
+};
+
+/// An `m.notice` room message with an HTML body and a plain text fallback.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MatrixRenderer;
+
+impl Renderer for MatrixRenderer {
+    type Output = Value;
+    fn render(&self, notification: &Notification, _style: &KindStyle) -> Value {
+        let times = [("Starts", notification.start()), ("Ends", notification.end())].map(|(name, time)| (name, format_time(time)));
+        let facts: Vec<_> = notification.facts().into_iter().chain(times).collect();
+        let body = std::iter::once(format!("{}: {notification}", notification.title()))
+            .chain(facts.iter().map(|(name, value)| format!("{name}: {value}")))
+            .collect::<Vec<_>>()
+            .join("\n")
+        ;
+        let items: String = facts.iter().map(|(name, value)| format!("<li><b>{name}</b>: {}</li>", escape_markup(value))).collect();
+        let formatted_body = format!("<h4>{}</h4><p>{}</p><ul>{items}</ul>", escape_markup(notification.title()), escape_markup(&notification.to_string()));
+        json!({
+            "msgtype": "m.notice",
+            "body": body,
+            "format": "org.matrix.custom.html",
+            "formatted_body": formatted_body,
+        })
+    }
+
+    fn render_alert(&self, text: &str) -> Value {
+        json!({ "msgtype": "m.notice", "body": text })
+    }
+}
+
+/// The homeserver's reply to a send or redact request.
+#[derive(Deserialize, Debug)]
+struct SentEvent {
+    event_id: String,
+}
+
+/// A Matrix room, `webhook_url` being `{homeserver}/_matrix/client/v3/rooms/{roomId}`
+/// and `access_token` the sending account's token.
+#[derive(Debug, Clone, Default)]
+pub struct MatrixRoom {
+    pub client: Client,
+    /// Shared by clones, so a retry reuses the transaction ID whichever clone sends it.
+    pub transactions: Arc<Transactions>,
+}
+
+impl MatrixRoom {
+    pub fn new(client: Client) -> Self {
+        Self { client, transactions: Default::default() }
+    }
+
+    /// `PUT`s `body` to `{room}/{path...}/{txnId}` with the destination's access token.
+    async fn send(&self, destination: &Destination, path: &[&str], body: &Value) -> Result<Response> {
+        let request = format!("{} {path:?} {body}", destination.webhook_url);
+        let mut url = Url::parse(&destination.webhook_url).map_err(|err| Error::Config(format!("invalid Matrix room URL: {err}")))?;
+        url.path_segments_mut()
+            .map_err(|()| Error::Config(format!("Matrix room URL can't have a path: {}", destination.webhook_url)))?
+            .pop_if_empty()
+            .extend(path)
+            .push(&self.transactions.begin(&request))
+        ;
+        let token = destination.access_token.as_deref().unwrap_or_default();
+        let result = match self.client.put(url).bearer_auth(token).json(body).send().await {
+            Ok(response) => check_status(response).await,
+            Err(err) => Err(err.into()),
+        };
+        if !matches!(&result, Err(err) if err.class() != ErrorClass::Permanent) {
+            self.transactions.finish(&request);
+        }
+        result
+    }
+}
+
+/// Transaction IDs of this run's requests that failed in a way a retry could fix, by request.
+///
+/// The homeserver drops a request whose transaction ID it has already seen, so a retry of one that did go through isn't posted twice.
+/// Requests that ran out of retries or whose destination got disabled are never finished, so the IDs are dropped at the end of each run.
+#[derive(Debug, Default)]
+pub struct Transactions(Mutex<HashMap<String, String>>);
+
+impl Transactions {
+    /// The transaction ID for `request`, the one of its last attempt if it is being retried.
+    fn begin(&self, request: &str) -> String {
+        self.pending().entry(request.to_owned()).or_insert_with(transaction_id).clone()
+    }
+
+    /// Forgets `request`'s transaction ID once it went through or won't be retried.
+    fn finish(&self, request: &str) {
+        self.pending().remove(request);
+    }
+
+    /// Forgets every transaction ID, the run's retries are over.
+    fn clear(&self) {
+        self.pending().clear();
+    }
+
+    fn pending(&self) -> std::sync::MutexGuard<'_, HashMap<String, String>> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Sender<Value> for MatrixRoom {
+    async fn post(&self, destination: &Destination, message: &Value) -> Result<Option<String>> {
+        let response = self.send(destination, &["send", "m.room.message"], message).await?;
+        let sent: SentEvent = serde_json::from_str(&response.text().await?)?;
+        Ok(Some(sent.event_id))
+    }
+
+    /// Sends an `m.replace` event, which clients show in place of the original.
+    async fn edit(&self, destination: &Destination, message_id: &str, message: &Value) -> Result<()> {
+        let mut replacement = message.clone();
+        replacement["m.new_content"] = message.clone();
+        replacement["m.relates_to"] = json!({ "rel_type": "m.replace", "event_id": message_id });
+        replacement["body"] = json!(format!("* {}", message["body"].as_str().unwrap_or_default()));
+        self.send(destination, &["send", "m.room.message"], &replacement).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, destination: &Destination, message_id: &str) -> Result<()> {
+        self.send(destination, &["redact", message_id], &json!({})).await?;
+        Ok(())
+    }
+
+    fn end_run(&self) {
+        self.transactions.clear();
+    }
+}
+
+/// Unique per request so the homeserver doesn't mistake a new event for a retried one, see [`Transactions`].
+fn transaction_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_millis());
+    format!("splatink-{millis}-{}", COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn unfinished_transactions_are_dropped_at_the_end_of_a_run() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT")).respond_with(ResponseTemplate::new(502)).mount(&server).await;
+        let room = MatrixRoom::default();
+        let destination = Destination {
+            webhook_url: format!("{}/_matrix/client/v3/rooms/!room:example.org", server.uri()),
+            ..Default::default()
+        };
+
+        // Never retried, say because the run was out of retries
+        assert!(room.delete(&destination, "$event").await.is_err());
+        assert_eq!(room.transactions.pending().len(), 1);
+        room.end_run();
+        assert!(room.transactions.pending().is_empty());
+    }
+}
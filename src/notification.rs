@@ -0,0 +1,543 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::KindStyle, pvp::PvpMode, reminder::Moment, splatfest::region_codes, splatfest_data::{self, Region}};
+
+/// Something worth announcing, independent of where it is sent.
+#[derive(Debug, Clone)]
+pub enum Notification {
+    Splatfest {
+        title: String,
+        teams: [String; 3],
+        team_image: String,
+        start: DateTime<Utc>,
+        tricolor: DateTime<Utc>,
+        end: DateTime<Utc>,
+        regions: Vec<Region>,
+    },
+    BigRun {
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        king: String,
+        stage: (String, String),
+    },
+    EggstraWork {
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        weapons: [String; 4],
+        stage: (String, String),
+    },
+    Random {
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        weapons: Vec<String>,
+        king: String,
+        stage: (String, String),
+    },
+    /// Every weapon is a golden question mark, drawn from the Grizzco weapons.
+    Golden {
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        king: String,
+        stage: (String, String),
+        /// The golden question mark, used as the thumbnail unless one is configured.
+        weapon_image: String,
+    },
+    /// A regular Salmon Run rotation with a weapon or stage from the watchlist.
+    WatchedRotation {
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        weapons: Vec<String>,
+        king: String,
+        stage: (String, String),
+        /// Names of the watched weapons and stage that showed up.
+        watched: Vec<String>,
+        /// Role IDs of the matching watchlist entries.
+        roles: Vec<String>,
+    },
+    /// An already announced rotation that splatoon3.ink has since revised.
+    Correction {
+        original: Box<Notification>,
+        changes: Vec<FieldChange>,
+        image: String,
+    },
+    /// An already announced rotation that has disappeared from the schedule before starting.
+    Cancellation {
+        original: Box<Notification>,
+    },
+    /// A Regular, Anarchy or X Battle rotation matching a subscription.
+    PvpRotation {
+        mode: PvpMode,
+        rule: String,
+        stages: [(String, String); 2],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        /// Role IDs of the matching subscriptions.
+        roles: Vec<String>,
+    },
+    Challenge {
+        name: String,
+        desc: String,
+        regulation: String,
+        rule: Option<String>,
+        stages: [(String, String); 2],
+        periods: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+    },
+    /// Final standings once a Splatfest has closed.
+    SplatfestResults {
+        title: String,
+        image: String,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        teams: Box<[TeamResult; 3]>,
+        regions: Vec<Region>,
+    },
+    /// A heads-up that `moment` of an already announced event is `offset` away.
+    Reminder {
+        original: Box<Notification>,
+        moment: Moment,
+        at: DateTime<Utc>,
+        offset: TimeDelta,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A team's standing in [`Notification::SplatfestResults`].
+pub struct TeamResult {
+    pub name: String,
+    pub color: u32,
+    pub result: splatfest_data::result,
+}
+
+/// Reads a category's ratio and whether the team topped it, `None` when the category wasn't played.
+type CategoryRatio = fn(&splatfest_data::result) -> Option<(f64, bool)>;
+
+impl TeamResult {
+    pub(crate) const CATEGORIES: [(&'static str, CategoryRatio); 5] = [
+        ("Conch Shells", |result| Some((result.horagaiRatio, result.isHoragaiRatioTop))),
+        ("Votes", |result| Some((result.voteRatio, result.isVoteRatioTop))),
+        ("Open", |result| Some((result.regularContributionRatio, result.isRegularContributionRatioTop))),
+        ("Pro", |result| Some((result.challengeContributionRatio, result.isChallengeContributionRatioTop))),
+        ("Tricolor", |result| result.tricolorContributionRatio.zip(result.isTricolorContributionRatioTop)),
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// One revised field of a [`Notification::Correction`].
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+
+/// What a destination or config section can select notifications by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    Splatfest,
+    BigRun,
+    EggstraWork,
+    Random,
+    Golden,
+    Watched,
+    Pvp,
+    Challenge,
+}
+
+impl NotificationKind {
+    pub const ALL: [NotificationKind; 8] = [
+        NotificationKind::Splatfest,
+        NotificationKind::BigRun,
+        NotificationKind::EggstraWork,
+        NotificationKind::Random,
+        NotificationKind::Golden,
+        NotificationKind::Watched,
+        NotificationKind::Pvp,
+        NotificationKind::Challenge,
+    ];
+
+    /// Infix of this kind's `SPLATINK_<KIND>_<FIELD>` variables.
+    pub fn env_name(&self) -> &'static str {
+        match self {
+            NotificationKind::Splatfest => "SPLATFEST",
+            NotificationKind::BigRun => "BIG_RUN",
+            NotificationKind::EggstraWork => "EGGSTRA_WORK",
+            NotificationKind::Random => "RANDOM",
+            NotificationKind::Golden => "GOLDEN",
+            NotificationKind::Watched => "WATCHED",
+            NotificationKind::Pvp => "PVP",
+            NotificationKind::Challenge => "CHALLENGE",
+        }
+    }
+
+    const THUMBNAIL_SPLATFEST: &'static str = r#"https://cdn.discordapp.com/attachments/842036323652337690/1259640933893275711/SfOpenSche.png?ex=668c6b89&is=668b1a09&hm=2cf5bd8276ae08f1791aa27590f2d4546166754df40ae8ee96e536f4a9a65769&"#;
+    const THUMBNAIL_BIG_RUN: &'static str = r#"https://cdn.wikimg.net/en/splatoonwiki/images/7/73/S3_Badge_Big_Run_Top_50_Percent.png"#;
+    const THUMBNAIL_EGGSTRA_WORK: &'static str = r#"https://cdn.wikimg.net/en/splatoonwiki/images/3/36/S3_Badge_Eggstra_Work_Top_5_Percent.png"#;
+    const THUMBNAIL_RANDOM: &'static str = r#"https://splatoon3.ink/assets/splatnet/v2/ui_img/473fffb2442075078d8bb7125744905abdeae651b6a5b7453ae295582e45f7d1_0.png"#;
+    const AVATAR_SPLATFEST: &'static str = r#"https://cdn.discordapp.com/attachments/842036323652337690/1259640933893275711/SfOpenSche.png?ex=668c6b89&is=668b1a09&hm=2cf5bd8276ae08f1791aa27590f2d4546166754df40ae8ee96e536f4a9a65769&"#;
+    const AVATAR_GRIZZCO: &'static str = r#"https://cdn.wikimg.net/en/splatoonwiki/images/8/8a/S3_Brand_Grizzco.png?20240224045446"#;
+    const NAME_SPLATFEST: &'static str = r#"Fax Machine"#;
+    const NAME_GRIZZCO: &'static str = r#"Grizzco"#;
+    const NAME_DEEP_CUT: &'static str = r#"Deep Cut"#;
+
+    /// Built-in presentation used for anything the config leaves out. There is no default ping.
+    pub fn default_style(&self) -> KindStyle {
+        let (username, avatar, thumbnail) = match self {
+            NotificationKind::Splatfest => (Self::NAME_SPLATFEST, Some(Self::AVATAR_SPLATFEST), Some(Self::THUMBNAIL_SPLATFEST)),
+            NotificationKind::BigRun => (Self::NAME_GRIZZCO, Some(Self::AVATAR_GRIZZCO), Some(Self::THUMBNAIL_BIG_RUN)),
+            NotificationKind::EggstraWork => (Self::NAME_GRIZZCO, Some(Self::AVATAR_GRIZZCO), Some(Self::THUMBNAIL_EGGSTRA_WORK)),
+            NotificationKind::Random => (Self::NAME_GRIZZCO, Some(Self::AVATAR_GRIZZCO), Some(Self::THUMBNAIL_RANDOM)),
+            // Falls back to the rotation's own golden question mark
+            NotificationKind::Golden => (Self::NAME_GRIZZCO, Some(Self::AVATAR_GRIZZCO), None),
+            NotificationKind::Watched => (Self::NAME_GRIZZCO, Some(Self::AVATAR_GRIZZCO), None),
+            NotificationKind::Pvp |
+            NotificationKind::Challenge => (Self::NAME_DEEP_CUT, None, None),
+        };
+        KindStyle {
+            ping: None,
+            username: username.to_owned(),
+            avatar: avatar.map(str::to_owned),
+            thumbnail: thumbnail.map(str::to_owned),
+        }
+    }
+}
+
+impl Display for NotificationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotificationKind::Splatfest => write!(f, "splatfest"),
+            NotificationKind::BigRun => write!(f, "big_run"),
+            NotificationKind::EggstraWork => write!(f, "eggstra_work"),
+            NotificationKind::Random => write!(f, "random"),
+            NotificationKind::Golden => write!(f, "golden"),
+            NotificationKind::Watched => write!(f, "watched"),
+            NotificationKind::Pvp => write!(f, "pvp"),
+            NotificationKind::Challenge => write!(f, "challenge"),
+        }
+    }
+}
+
+impl Notification {
+    /// The kind this is routed and styled as, follow-ups take their original's kind.
+    pub fn kind(&self) -> NotificationKind {
+        match self {
+            Notification::Splatfest{..} |
+            Notification::SplatfestResults{..} => NotificationKind::Splatfest,
+            Notification::BigRun{..} => NotificationKind::BigRun,
+            Notification::EggstraWork{..} => NotificationKind::EggstraWork,
+            Notification::Random{..} => NotificationKind::Random,
+            Notification::Golden{..} => NotificationKind::Golden,
+            Notification::WatchedRotation{..} => NotificationKind::Watched,
+            Notification::PvpRotation{..} => NotificationKind::Pvp,
+            Notification::Challenge{..} => NotificationKind::Challenge,
+            Notification::Correction{original, ..} |
+            Notification::Cancellation{original} |
+            Notification::Reminder{original, ..} => original.kind(),
+        }
+    }
+
+    /// What this says about the event behind [`Self::key`], which its announcement, corrections, cancellation and reminders share.
+    ///
+    /// Corrections carry the values they correct to, so a later revision of the same event is a different one.
+    pub fn variant(&self) -> String {
+        match self {
+            Notification::Correction{changes, ..} => {
+                let values: Vec<_> = changes.iter().map(|change| format!("{}={}", change.field, change.new)).collect();
+                format!("correction:{}", values.join(";"))
+            },
+            Notification::Cancellation{..} => "cancellation".to_owned(),
+            Notification::Reminder{..} => "reminder".to_owned(),
+            _ => "announcement".to_owned(),
+        }
+    }
+
+    /// Identifies the announced event so later messages about it can find the original.
+    pub fn key(&self) -> String {
+        match self {
+            Notification::Correction{original, ..} |
+            Notification::Cancellation{original} => original.key(),
+            // Region-exclusive fests in different regions can start at the same time
+            Notification::Splatfest{start, regions, ..} if regions.len() < Region::ALL.len() =>
+                format!("{}:{}:{}", self.kind(), start.timestamp(), region_codes(regions)),
+            Notification::SplatfestResults{start, regions, ..} if regions.len() < Region::ALL.len() =>
+                format!("{}-results:{}:{}", self.kind(), start.timestamp(), region_codes(regions)),
+            Notification::SplatfestResults{start, ..} => format!("{}-results:{}", self.kind(), start.timestamp()),
+            // Several modes share a time slot
+            Notification::PvpRotation{mode, start, ..} => format!("{}:{mode:?}:{}", self.kind(), start.timestamp()),
+            Notification::Reminder{original, moment, offset, ..} => format!("reminder:{}:{moment}:{}", original.key(), offset.num_minutes()),
+            _ => format!("{}:{}", self.kind(), self.start().timestamp()),
+        }
+    }
+
+    /// Times an event can be reminded about.
+    pub fn moments(&self) -> Vec<(Moment, DateTime<Utc>)> {
+        match self {
+            Notification::Splatfest{start, tricolor, end, ..} => vec![(Moment::Start, *start), (Moment::Tricolor, *tricolor), (Moment::End, *end)],
+            Notification::BigRun{start, ..} |
+            Notification::EggstraWork{start, ..} |
+            Notification::Random{start, ..} |
+            Notification::Golden{start, ..} => vec![(Moment::Start, *start)],
+            Notification::WatchedRotation{..} |
+            Notification::PvpRotation{..} |
+            Notification::Challenge{..} |
+            Notification::SplatfestResults{..} |
+            Notification::Correction{..} |
+            Notification::Cancellation{..} |
+            Notification::Reminder{..} => vec![],
+        }
+    }
+
+    /// Splatfest regions this is about, `None` for anything that isn't region specific.
+    pub fn regions(&self) -> Option<&[Region]> {
+        match self {
+            Notification::Splatfest{regions, ..} |
+            Notification::SplatfestResults{regions, ..} => Some(regions),
+            Notification::Correction{original, ..} |
+            Notification::Cancellation{original} |
+            Notification::Reminder{original, ..} => original.regions(),
+            _ => None,
+        }
+    }
+
+    /// Names the regions of a fest that isn't held everywhere.
+    pub(crate) fn region_label(&self) -> Option<String> {
+        let regions = self.regions().filter(|regions| regions.len() < Region::ALL.len())?;
+        Some(format!("{} only", regions.iter().map(Region::to_string).collect::<Vec<_>>().join(", ")))
+    }
+
+    pub fn start(&self) -> DateTime<Utc> {
+        match self {
+            Notification::Splatfest{start, ..} |
+            Notification::SplatfestResults{start, ..} |
+            Notification::BigRun{start, ..} |
+            Notification::EggstraWork{start, ..} |
+            Notification::Random{start, ..} |
+            Notification::Golden{start, ..} |
+            Notification::WatchedRotation{start, ..} |
+            Notification::PvpRotation{start, ..} => *start,
+            Notification::Challenge{periods, ..} => periods.iter().map(|(start, _)| *start).min().unwrap_or_default(),
+            Notification::Correction{original, ..} |
+            Notification::Cancellation{original} |
+            Notification::Reminder{original, ..} => original.start(),
+        }
+    }
+
+    pub fn end(&self) -> DateTime<Utc> {
+        match self {
+            Notification::Splatfest{end, ..} |
+            Notification::SplatfestResults{end, ..} |
+            Notification::BigRun{end, ..} |
+            Notification::EggstraWork{end, ..} |
+            Notification::Random{end, ..} |
+            Notification::Golden{end, ..} |
+            Notification::WatchedRotation{end, ..} |
+            Notification::PvpRotation{end, ..} => *end,
+            Notification::Challenge{periods, ..} => periods.iter().map(|(_, end)| *end).max().unwrap_or_default(),
+            Notification::Correction{original, ..} |
+            Notification::Cancellation{original} |
+            Notification::Reminder{original, ..} => original.end(),
+        }
+    }
+
+    pub const TITLE_SPLATFEST: &'static str = "A Splatfest has been announced!";
+    pub const TITLE_SPLATFEST_RESULTS: &'static str = "The Splatfest results are in!";
+    pub const TITLE_BIG_RUN: &'static str = "A Big Run alert has been broadcasted!";
+    pub const TITLE_EGGSTRA_WORK: &'static str = "Eggstra Workers are needed at Grizzco!";
+    pub const TITLE_SINGLE_RANDOM: &'static str = "A Single Random Rotation has been added to the schedule!";
+    pub const TITLE_PARTIAL_RANDOM: &'static str = "A Partial Random Rotation has been added to the schedule!";
+    pub const TITLE_FULL_RANDOM: &'static str = "A Random Rotation has been added to the schedule!";
+    pub const TITLE_GOLDEN: &'static str = "A Golden Rotation has been added to the schedule!";
+    pub const TITLE_WATCHED: &'static str = "A Salmon Run rotation you're watching has been added to the schedule!";
+    pub const TITLE_PVP: &'static str = "A rotation you're watching is on the schedule!";
+    pub const TITLE_CHALLENGE: &'static str = "A new Challenge has been announced!";
+    pub const TITLE_CORRECTION: &'static str = "A scheduled rotation has been changed!";
+    pub const TITLE_CANCELLATION: &'static str = "A scheduled rotation has been cancelled!";
+    pub const TITLE_STARTING_SOON: &'static str = "Starting soon!";
+    pub const TITLE_STARTING_NOW: &'static str = "Starting now!";
+    pub const TITLE_TRICOLOR_SOON: &'static str = "Tricolor Battles are about to begin!";
+    pub const TITLE_TRICOLOR_NOW: &'static str = "Tricolor Battles have begun!";
+    pub const TITLE_ENDING_SOON: &'static str = "The Splatfest is ending soon!";
+    pub const TITLE_ENDING_NOW: &'static str = "The Splatfest has ended!";
+    pub fn title(&self) -> &'static str {
+        match self {
+            Notification::Splatfest{..} => Self::TITLE_SPLATFEST,
+            Notification::SplatfestResults{..} => Self::TITLE_SPLATFEST_RESULTS,
+            Notification::BigRun{..} => Self::TITLE_BIG_RUN,
+            Notification::EggstraWork{..} => Self::TITLE_EGGSTRA_WORK,
+            Notification::Random{weapons, ..} => {
+                match weapons.len() {
+                    0 | 1 => Self::TITLE_SINGLE_RANDOM,
+                    2 | 3 => Self::TITLE_PARTIAL_RANDOM,
+                    _ => Self::TITLE_FULL_RANDOM
+                }
+            },
+            Notification::Golden{..} => Self::TITLE_GOLDEN,
+            Notification::WatchedRotation{..} => Self::TITLE_WATCHED,
+            Notification::PvpRotation{..} => Self::TITLE_PVP,
+            Notification::Challenge{..} => Self::TITLE_CHALLENGE,
+            Notification::Correction{..} => Self::TITLE_CORRECTION,
+            Notification::Cancellation{..} => Self::TITLE_CANCELLATION,
+            Notification::Reminder{moment, offset, ..} => match (moment, offset.is_zero()) {
+                (Moment::Start, false) => Self::TITLE_STARTING_SOON,
+                (Moment::Start, true) => Self::TITLE_STARTING_NOW,
+                (Moment::Tricolor, false) => Self::TITLE_TRICOLOR_SOON,
+                (Moment::Tricolor, true) => Self::TITLE_TRICOLOR_NOW,
+                (Moment::End, false) => Self::TITLE_ENDING_SOON,
+                (Moment::End, true) => Self::TITLE_ENDING_NOW,
+            },
+        }
+    }
+
+    pub const COLOR_SPLATFEST: u32 = 0x2f5dd4;
+    pub const COLOR_BIG_RUN: u32 = 0xB322FF;
+    pub const COLOR_RANDOM: u32 = 0x00D82D;
+    pub const COLOR_EGGSTRA_WORK: u32 = 0xD18E14;
+    pub const COLOR_GOLDEN: u32 = 0xFFC824;
+    pub const COLOR_WATCHED: u32 = 0xFF6A00;
+    pub const COLOR_CORRECTION: u32 = 0xF0B232;
+    pub const COLOR_REGULAR: u32 = 0x19D719;
+    pub const COLOR_ANARCHY: u32 = 0xF54910;
+    pub const COLOR_X: u32 = 0x0FDB9B;
+    pub const COLOR_CHALLENGE: u32 = 0xF02D7D;
+    pub fn color(&self) -> u32 {
+        match self {
+            Notification::Splatfest{..} => Self::COLOR_SPLATFEST,
+            Notification::SplatfestResults{teams, ..} => teams.iter()
+                .find(|team| team.result.isWinner)
+                .map_or(Self::COLOR_SPLATFEST, |team| team.color),
+            Notification::BigRun{..} => Self::COLOR_BIG_RUN,
+            Notification::Random{..} => Self::COLOR_RANDOM,
+            Notification::EggstraWork{..} => Self::COLOR_EGGSTRA_WORK,
+            Notification::Golden{..} => Self::COLOR_GOLDEN,
+            Notification::WatchedRotation{..} => Self::COLOR_WATCHED,
+            Notification::PvpRotation{mode, ..} => match mode {
+                PvpMode::Regular => Self::COLOR_REGULAR,
+                PvpMode::AnarchySeries |
+                PvpMode::AnarchyOpen => Self::COLOR_ANARCHY,
+                PvpMode::X => Self::COLOR_X,
+            },
+            Notification::Challenge{..} => Self::COLOR_CHALLENGE,
+            Notification::Correction{..} |
+            Notification::Cancellation{..} => Self::COLOR_CORRECTION,
+            Notification::Reminder{original, ..} => original.color(),
+        }
+    }
+
+    /// Thumbnail taken from the data itself, for kinds without a built-in one.
+    pub fn thumbnail(&self) -> Option<&str> {
+        match self {
+            Notification::Golden{weapon_image, ..} => Some(weapon_image),
+            Notification::Correction{original, ..} |
+            Notification::Cancellation{original} |
+            Notification::Reminder{original, ..} => original.thumbnail(),
+            _ => None,
+        }
+    }
+
+    /// The main picture: a stage, the fest's team art or the like.
+    pub fn image(&self) -> Option<&str> {
+        match self {
+            Notification::Splatfest{team_image: image, ..} |
+            Notification::SplatfestResults{image, ..} |
+            Notification::Correction{image, ..} => Some(image),
+            Notification::BigRun{stage, ..} |
+            Notification::EggstraWork{stage, ..} |
+            Notification::Random{stage, ..} |
+            Notification::Golden{stage, ..} |
+            Notification::WatchedRotation{stage, ..} => Some(&stage.1),
+            Notification::PvpRotation{stages, ..} |
+            Notification::Challenge{stages, ..} => Some(&stages[0].1),
+            Notification::Cancellation{original} |
+            Notification::Reminder{original, ..} => original.image(),
+        }
+    }
+
+    /// Name and value pairs for backends without Discord's embed layout.
+    pub fn facts(&self) -> Vec<(&'static str, String)> {
+        let mut facts = match self {
+            Notification::Splatfest{teams, tricolor, ..} => vec![
+                ("Teams", teams.join(", ")),
+                ("Tricolor", format_time(*tricolor)),
+            ],
+            Notification::SplatfestResults{teams, ..} => vec![
+                ("Winner", teams.iter().find(|team| team.result.isWinner).map_or("Nobody", |team| team.name.as_str()).to_owned()),
+            ],
+            Notification::BigRun{king, stage, ..} |
+            Notification::Golden{king, stage, ..} => vec![("King Salmonid", king.clone()), ("Stage", stage.0.clone())],
+            Notification::EggstraWork{weapons, stage, ..} => vec![("Weapons", weapons.join(", ")), ("Stage", stage.0.clone())],
+            Notification::Random{weapons, king, stage, ..} => vec![
+                ("Weapons", weapons.join(", ")),
+                ("King Salmonid", king.clone()),
+                ("Stage", stage.0.clone()),
+            ],
+            Notification::WatchedRotation{weapons, king, stage, watched, ..} => vec![
+                ("Watching", watched.join(", ")),
+                ("Weapons", weapons.join(", ")),
+                ("King Salmonid", king.clone()),
+                ("Stage", stage.0.clone()),
+            ],
+            Notification::PvpRotation{mode, rule, stages, ..} => vec![
+                ("Mode", mode.to_string()),
+                ("Rule", rule.clone()),
+                ("Stages", format!("{}, {}", stages[0].0, stages[1].0)),
+            ],
+            Notification::Challenge{rule, stages, periods, ..} => rule.iter().map(|rule| ("Rule", rule.clone()))
+                .chain([("Stages", format!("{}, {}", stages[0].0, stages[1].0)), ("Time periods", periods.len().to_string())])
+                .collect(),
+            Notification::Correction{changes, ..} => changes.iter().map(|change| (change.field, format!("{} → {}", change.old, change.new))).collect(),
+            Notification::Cancellation{..} => vec![],
+            // Already labelled with the original's regions
+            Notification::Reminder{original, ..} => return original.facts(),
+        };
+        if let Some(label) = self.region_label() {
+            facts.push(("Regions", label));
+        }
+        facts
+    }
+
+    /// Role mentions for the message content.
+    pub fn ping(&self, style: &KindStyle) -> Option<String> {
+        match self {
+            Notification::PvpRotation{roles, ..} |
+            Notification::WatchedRotation{roles, ..} if !roles.is_empty() => Some(roles.iter().map(|role| format!("<@&{role}>")).collect::<Vec<_>>().join(" ")),
+            _ => style.ping.clone(),
+        }
+    }
+}
+
+impl Display for Notification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Notification::Splatfest{title, ..} => match self.region_label() {
+                Some(label) => write!(f, "Splatfest: {title} ({label})"),
+                None => write!(f, "Splatfest: {title}"),
+            },
+            Notification::SplatfestResults{title, ..} => match self.region_label() {
+                Some(label) => write!(f, "Splatfest results: {title} ({label})"),
+                None => write!(f, "Splatfest results: {title}"),
+            },
+            Notification::BigRun{stage, ..} => write!(f, "Big Run on {}", stage.0),
+            Notification::EggstraWork{stage, ..} => write!(f, "Eggstra Work on {}", stage.0),
+            Notification::Random{stage, weapons, ..} => match weapons.len() {
+                0 | 1 => write!(f, "Random Rotation on {}", stage.0),
+                2 | 3 => write!(f, "Random Rotation on {}", stage.0),
+                _ => write!(f, "Random Rotation on {}", stage.0),
+            },
+            Notification::Golden{stage, ..} => write!(f, "Golden Rotation on {}", stage.0),
+            Notification::WatchedRotation{stage, watched, ..} => write!(f, "Watched rotation on {} ({})", stage.0, watched.join(", ")),
+            Notification::PvpRotation{mode, rule, stages, ..} => write!(f, "{mode} {rule} on {} and {}", stages[0].0, stages[1].0),
+            Notification::Challenge{name, ..} => write!(f, "Challenge: {name}"),
+            Notification::Correction{original, ..} => write!(f, "Correction to {original}"),
+            Notification::Cancellation{original} => write!(f, "Cancellation of {original}"),
+            Notification::Reminder{original, moment, offset, ..} => write!(f, "Reminder ({moment} -{}m) for {original}", offset.num_minutes()),
+        }
+    }
+}
+
+/// Plain text timestamp for backends without Discord's `<t:...>` markup.
+pub fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M UTC").to_string()
+}
@@ -0,0 +1,133 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::{diff::added, schedule_data::{data, eventSchedule, vsRule, vsStage}, Notification};
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct PvpConfig {
+    /// Rotations worth announcing, nothing is announced when empty.
+    pub subscriptions: Vec<PvpSubscription>,
+    /// Announce every new Challenge.
+    pub challenges: bool,
+}
+
+/// Matches rotations by mode, rule and stage. Left out fields match anything.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct PvpSubscription {
+    pub mode: Option<PvpMode>,
+    /// Rule name like `Clam Blitz` or its ID like `CLAM`.
+    pub rule: Option<String>,
+    /// Either stage of the rotation.
+    pub stage: Option<String>,
+    /// Role ID pinged instead of the `pvp` kind's role.
+    pub role: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PvpMode {
+    Regular,
+    AnarchySeries,
+    AnarchyOpen,
+    X,
+}
+
+impl Display for PvpMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PvpMode::Regular => write!(f, "Regular Battle"),
+            PvpMode::AnarchySeries => write!(f, "Anarchy Battle (Series)"),
+            PvpMode::AnarchyOpen => write!(f, "Anarchy Battle (Open)"),
+            PvpMode::X => write!(f, "X Battle"),
+        }
+    }
+}
+
+impl PvpSubscription {
+    fn matches(&self, mode: PvpMode, rule: &vsRule, stages: &[vsStage; 2]) -> bool {
+        self.mode.is_none_or(|wanted| wanted == mode)
+            && self.rule.as_ref().is_none_or(|wanted| wanted.eq_ignore_ascii_case(&rule.name) || wanted.eq_ignore_ascii_case(&rule.rule))
+            && self.stage.as_ref().is_none_or(|wanted| stages.iter().any(|stage| wanted.eq_ignore_ascii_case(&stage.name)))
+    }
+}
+
+/// A single mode's rotation flattened out of whichever schedule it came from.
+struct Slot<'a> {
+    mode: PvpMode,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    rule: &'a vsRule,
+    stages: &'a [vsStage; 2],
+}
+
+/// Announcements for newly scheduled rotations that match a subscription.
+pub fn rotation_notifications(config: &PvpConfig, file_data: &data, internet_data: &data) -> Vec<Notification> {
+    if config.subscriptions.is_empty() {
+        return vec![];
+    }
+    let regular = added(&file_data.regularSchedules.nodes, &internet_data.regularSchedules.nodes);
+    let bankara = added(&file_data.bankaraSchedules.nodes, &internet_data.bankaraSchedules.nodes);
+    let x = added(&file_data.xSchedules.nodes, &internet_data.xSchedules.nodes);
+    let slots = regular.iter()
+        .filter_map(|node| node.regularMatchSetting.as_ref().map(|setting| Slot {
+            mode: PvpMode::Regular,
+            start: node.startTime.to_utc(),
+            end: node.endTime.to_utc(),
+            rule: &setting.vsRule,
+            stages: &setting.vsStages,
+        }))
+        .chain(bankara.iter().flat_map(|node| node.bankaraMatchSettings.iter().flat_map(|(series, open)| [series, open]).map(|setting| Slot {
+            mode: match setting.bankaraMode.as_str() {
+                "OPEN" => PvpMode::AnarchyOpen,
+                _ => PvpMode::AnarchySeries,
+            },
+            start: node.startTime.to_utc(),
+            end: node.endTime.to_utc(),
+            rule: &setting.vsRule,
+            stages: &setting.vsStages,
+        })))
+        .chain(x.iter().filter_map(|node| node.xMatchSetting.as_ref().map(|setting| Slot {
+            mode: PvpMode::X,
+            start: node.startTime.to_utc(),
+            end: node.endTime.to_utc(),
+            rule: &setting.vsRule,
+            stages: &setting.vsStages,
+        })))
+    ;
+    slots.filter_map(|slot| {
+        let matched: Vec<_> = config.subscriptions.iter().filter(|subscription| subscription.matches(slot.mode, slot.rule, slot.stages)).collect();
+        (!matched.is_empty()).then(|| Notification::PvpRotation {
+            mode: slot.mode,
+            rule: slot.rule.name.clone(),
+            stages: slot.stages.clone().map(|stage| (stage.name, stage.image.url)),
+            start: slot.start,
+            end: slot.end,
+            roles: matched.iter().filter_map(|subscription| subscription.role.clone()).collect(),
+        })
+    })
+    .collect()
+}
+
+/// Announcements for newly scheduled Challenges.
+pub fn challenge_notifications(config: &PvpConfig, file_data: &data, internet_data: &data) -> Vec<Notification> {
+    if !config.challenges {
+        return vec![];
+    }
+    added(&file_data.eventSchedules.nodes, &internet_data.eventSchedules.nodes).iter().map(challenge_notification).collect()
+}
+
+fn challenge_notification(event: &eventSchedule) -> Notification {
+    let setting = &event.leagueMatchSetting;
+    Notification::Challenge {
+        name: setting.leagueMatchEvent.name.clone(),
+        desc: setting.leagueMatchEvent.desc.clone(),
+        regulation: setting.leagueMatchEvent.regulation.replace("<br />", "\n"),
+        rule: setting.vsRule.as_ref().map(|rule| rule.name.clone()),
+        stages: setting.vsStages.clone().map(|stage| (stage.name, stage.image.url)),
+        periods: event.timePeriods.iter().map(|period| (period.startTime.to_utc(), period.endTime.to_utc())).collect(),
+    }
+}
@@ -0,0 +1,118 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use reqwest::header::HeaderMap;
+use tokio::time::Instant;
+
+/// Discord's rate limits as learned from response headers, shared by every request of a [`DiscordWebhook`](crate::send::DiscordWebhook).
+///
+/// Routes are mapped to the bucket Discord reports for them, so webhooks sharing a bucket also share its budget.
+#[derive(Debug, Default)]
+pub struct RateLimits {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Route to the `X-RateLimit-Bucket` it was last answered with.
+    buckets_by_route: HashMap<String, String>,
+    buckets: HashMap<String, Bucket>,
+    /// Set by a global 429, nothing is sent before then.
+    global_until: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    remaining: u32,
+    reset_at: Instant,
+}
+
+impl RateLimits {
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Waits until a request on `route` fits in its bucket and the global limit, then takes one from the bucket.
+    pub async fn acquire(&self, route: &str) {
+        loop {
+            let wait = {
+                let mut state = self.state();
+                let now = Instant::now();
+                let global = state.global_until.filter(|&until| until > now);
+                let bucket = state.buckets_by_route.get(route).cloned().and_then(|bucket| state.buckets.get_mut(&bucket));
+                match (global, bucket) {
+                    (Some(until), _) => Some(until - now),
+                    (None, Some(bucket)) if bucket.reset_at <= now => None,
+                    (None, Some(bucket)) if bucket.remaining == 0 => Some(bucket.reset_at - now),
+                    (None, Some(bucket)) => {
+                        // Reserved so concurrent requests on a shared bucket don't all see the same budget
+                        bucket.remaining -= 1;
+                        None
+                    },
+                    (None, None) => None,
+                }
+            };
+            match wait {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => break,
+            }
+        }
+    }
+
+    /// Records the `X-RateLimit-*` headers of a response on `route`.
+    pub fn update(&self, route: &str, headers: &HeaderMap) {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        let (Some(remaining), Some(reset_after)) = (
+            header("x-ratelimit-remaining").and_then(|value| value.parse().ok()),
+            header("x-ratelimit-reset-after").and_then(|value| value.parse::<f64>().ok()),
+        ) else {
+            return;
+        };
+        let bucket = header("x-ratelimit-bucket").unwrap_or(route).to_owned();
+        let mut state = self.state();
+        state.buckets_by_route.insert(route.to_owned(), bucket.clone());
+        state.buckets.insert(bucket, Bucket {
+            remaining,
+            reset_at: Instant::now() + Duration::from_secs_f64(reset_after.max(0.0)),
+        });
+    }
+
+    /// Records a 429 on `route`, holding back every route if it was `global`.
+    pub fn limited(&self, route: &str, retry_after: Duration, global: bool) {
+        let until = Instant::now() + retry_after;
+        let mut state = self.state();
+        if global {
+            state.global_until = Some(state.global_until.map_or(until, |current| current.max(until)));
+            return;
+        }
+        let bucket = state.buckets_by_route.entry(route.to_owned()).or_insert_with(|| route.to_owned()).clone();
+        state.buckets.insert(bucket, Bucket { remaining: 0, reset_at: until });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(remaining: &str, reset_after: &str, bucket: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", remaining.parse().unwrap());
+        headers.insert("x-ratelimit-reset-after", reset_after.parse().unwrap());
+        headers.insert("x-ratelimit-bucket", bucket.parse().unwrap());
+        headers
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn exhausted_and_global_limits_are_waited_out() {
+        let limits = RateLimits::default();
+        limits.update("a", &headers("0", "2.5", "shared"));
+        limits.update("b", &headers("0", "2.5", "shared"));
+        let started = Instant::now();
+        limits.acquire("b").await;
+        assert_eq!(started.elapsed(), Duration::from_millis(2500));
+
+        limits.limited("c", Duration::from_secs(4), true);
+        let started = Instant::now();
+        limits.acquire("unrelated").await;
+        assert_eq!(started.elapsed(), Duration::from_secs(4));
+    }
+}
@@ -0,0 +1,81 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Deserialize;
+
+use crate::{Notification, NotificationKind};
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReminderConfig {
+    /// Minutes before each moment to send a reminder, `0` reminds as it happens. Empty disables reminders.
+    pub offsets_minutes: Vec<u32>,
+    /// Kinds to send reminders for, all of them when empty.
+    pub kinds: Vec<NotificationKind>,
+}
+
+/// A point in an event's life worth reminding people about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Moment {
+    Start,
+    Tricolor,
+    End,
+}
+
+impl Display for Moment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Moment::Start => write!(f, "start"),
+            Moment::Tricolor => write!(f, "tricolor"),
+            Moment::End => write!(f, "end"),
+        }
+    }
+}
+
+/// How long after an "as it happens" reminder was due it is still worth sending, e.g. after a restart.
+const LATE_GRACE: TimeDelta = TimeDelta::hours(1);
+
+struct Scheduled {
+    trigger: DateTime<Utc>,
+    deadline: DateTime<Utc>,
+    moment: Moment,
+    at: DateTime<Utc>,
+    offset: TimeDelta,
+}
+
+fn schedule<'a>(config: &'a ReminderConfig, event: &'a Notification) -> impl Iterator<Item = Scheduled> + 'a {
+    let enabled = config.kinds.is_empty() || config.kinds.contains(&event.kind());
+    let moments = if enabled { event.moments() } else { vec![] };
+    moments.into_iter().flat_map(move |(moment, at)| config.offsets_minutes.iter().map(move |&minutes| {
+        let offset = TimeDelta::minutes(minutes.into());
+        let deadline = match (minutes, moment) {
+            (0, Moment::Start) => event.end(),
+            (0, _) => at + LATE_GRACE,
+            _ => at,
+        };
+        Scheduled { trigger: at - offset, deadline, moment, at, offset }
+    }))
+}
+
+/// Reminders whose time has come at `now`. Callers skip ones that were already delivered.
+pub fn due(config: &ReminderConfig, events: &[Notification], now: DateTime<Utc>) -> Vec<Notification> {
+    events.iter()
+        .flat_map(|event| schedule(config, event).map(move |scheduled| (event, scheduled)))
+        .filter(|(_, scheduled)| scheduled.trigger <= now && now < scheduled.deadline)
+        .map(|(event, scheduled)| Notification::Reminder {
+            original: Box::new(event.clone()),
+            moment: scheduled.moment,
+            at: scheduled.at,
+            offset: scheduled.offset,
+        })
+        .collect()
+}
+
+/// The earliest reminder that isn't due yet, so the daemon can wake up for it.
+pub fn next(config: &ReminderConfig, events: &[Notification], now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    events.iter()
+        .flat_map(|event| schedule(config, event))
+        .map(|scheduled| scheduled.trigger)
+        .filter(|&trigger| trigger > now)
+        .min()
+}
@@ -0,0 +1,257 @@
+use webhook::models::{Embed, Message};
+
+use crate::{config::KindStyle, notification::{Notification, TeamResult}, reminder::Moment};
+
+/// Turns a [`Notification`] into the message a [`Sender`](crate::send::Sender) delivers.
+pub trait Renderer {
+    type Output;
+    /// Role pings in `style` are Discord IDs, so only backends that hand them on to Discord or the receiver use them.
+    fn render(&self, notification: &Notification, style: &KindStyle) -> Self::Output;
+    /// A plain text message for whoever runs the bot, e.g. a stale data warning.
+    fn render_alert(&self, text: &str) -> Self::Output;
+}
+
+/// A Discord webhook message with a single embed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiscordRenderer;
+
+impl Renderer for DiscordRenderer {
+    type Output = Message;
+    fn render(&self, notification: &Notification, style: &KindStyle) -> Message {
+        let mut message = Message::new();
+        notification.setup_message(style, &mut message);
+        message
+    }
+
+    fn render_alert(&self, text: &str) -> Message {
+        let mut message = Message::new();
+        message.content(&truncate(text, Self::CONTENT_LIMIT));
+        message
+    }
+}
+
+impl DiscordRenderer {
+    const CONTENT_LIMIT: usize = 2000;
+}
+
+impl Notification {
+    const EMBED_DESCRIPTION_LIMIT: usize = 4096;
+    const EMBED_FIELD_LIMIT: usize = 1024;
+
+    fn prefix_embed<'a>(&self, style: &KindStyle, embed: &'a mut Embed) -> &'a mut Embed {
+        embed
+            .title(self.title())
+            .color(self.color().to_string().as_str())
+        ;
+        if let Some(thumbnail) = style.thumbnail.as_deref().or(self.thumbnail()) {
+            embed.thumbnail(thumbnail);
+        }
+        embed
+    }
+
+    pub(crate) fn setup_message<'a>(&'a self, style: &KindStyle, message: &'a mut Message) -> &'a mut Message {
+        if let Some(ping) = self.ping(style) {
+            message.content(&ping);
+        }
+        if let Some(avatar) = &style.avatar {
+            message.avatar_url(avatar);
+        }
+        message.username(&style.username);
+        match self {
+            Notification::Splatfest{title, teams, team_image, start, tricolor, end, ..} => {
+                let start_stamp = start.timestamp();
+                let tricolor_stamp = tricolor.timestamp();
+                let end_stamp = end.timestamp();
+                message
+                    .embed(|embed| {
+                        self.prefix_embed(style, embed)
+                            .field(&format!("Starts <t:{start_stamp}:R>"), &format!("<t:{start_stamp}:f>"), true)
+                            .field(&format!("Tricolor <t:{tricolor_stamp}:R>"), &format!("<t:{tricolor_stamp}:f>"), true)
+                            .field(&format!("Ends <t:{end_stamp}:R>"), &format!("<t:{end_stamp}:f>"), true)
+                            .field(title, teams.iter().cloned().reduce(|acc, e| format!("{acc}\n{e}")).unwrap_or_default().as_str(), false)
+                        ;
+                        if let Some(label) = self.region_label() {
+                            embed.field("Regions", &label, false);
+                        }
+                        embed.image(team_image)
+                    })
+                ;
+            },
+            Notification::PvpRotation{mode, rule, stages, start, end, ..} => {
+                let start_stamp = start.timestamp();
+                let end_stamp = end.timestamp();
+                message
+                    .embed(|embed| self.prefix_embed(style, embed)
+                        .description(&format!("{mode}: {rule}"))
+                        .field(&format!("Starts <t:{start_stamp}:R>"), &format!("<t:{start_stamp}:f>"), true)
+                        .field(&format!("Ends <t:{end_stamp}:R>"), &format!("<t:{end_stamp}:f>"), true)
+                        .field("Stages", &format!("{}\n{}", stages[0].0, stages[1].0), false)
+                        .image(&stages[0].1)
+                    )
+                ;
+            },
+            Notification::Challenge{name, desc, regulation, rule, stages, periods} => {
+                let times = periods.iter()
+                    .map(|(start, end)| format!("<t:{}:f> - <t:{}:t>", start.timestamp(), end.timestamp()))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+                ;
+                message
+                    .embed(|embed| {
+                        self.prefix_embed(style, embed)
+                            .description(&truncate(&format!("**{name}**\n{desc}"), Self::EMBED_DESCRIPTION_LIMIT))
+                            .field("Times", &truncate(&times, Self::EMBED_FIELD_LIMIT), false)
+                        ;
+                        if let Some(rule) = rule {
+                            embed.field("Rule", rule, true);
+                        }
+                        embed
+                            .field("Stages", &format!("{}\n{}", stages[0].0, stages[1].0), true)
+                            .field("Regulation", &truncate(regulation, Self::EMBED_FIELD_LIMIT), false)
+                            .image(&stages[0].1)
+                    })
+                ;
+            },
+            Notification::SplatfestResults{title, image, teams, ..} => {
+                let winner = teams.iter().find(|team| team.result.isWinner).map_or("Nobody", |team| team.name.as_str());
+                message
+                    .embed(|embed| {
+                        self.prefix_embed(style, embed)
+                            .description(&format!("{title}\nTeam {winner} wins!"))
+                        ;
+                        if let Some(label) = self.region_label() {
+                            embed.field("Regions", &label, false);
+                        }
+                        for (category, ratio) in TeamResult::CATEGORIES {
+                            let lines: Option<Vec<_>> = teams.iter().map(|team| ratio(&team.result).map(|(ratio, top)| match top {
+                                true => format!("**{}: {:.2}%**", team.name, ratio * 100.0),
+                                false => format!("{}: {:.2}%", team.name, ratio * 100.0),
+                            })).collect();
+                            if let Some(lines) = lines {
+                                embed.field(category, &lines.join("\n"), true);
+                            }
+                        }
+                        embed.image(image)
+                    })
+                ;
+            },
+            Notification::EggstraWork{start, end, weapons, stage} => {
+                let start_stamp = start.timestamp();
+                let end_stamp = end.timestamp();
+                message
+                    .embed(|embed| self.prefix_embed(style, embed)
+                        .field(&format!("Starts <t:{start_stamp}:R>"), &format!("<t:{start_stamp}:f>"), true)
+                        .field(&format!("Ends <t:{end_stamp}:R>"), &format!("<t:{end_stamp}:f>"), true)
+                        .field("Weapons", weapons.iter().cloned().reduce(|acc, e| format!("{acc}\n{e}")).unwrap_or_default().as_str(), false)
+                        .field("Stage", &stage.0, false)
+                        .image(&stage.1)
+                    )
+                ;
+            },
+            Notification::Random{start, end, weapons, king, stage} => {
+                let start_stamp = start.timestamp();
+                let end_stamp = end.timestamp();
+                message
+                    .embed(|embed| self.prefix_embed(style, embed)
+                        .field(&format!("Starts <t:{start_stamp}:R>"), &format!("<t:{start_stamp}:f>"), true)
+                        .field(&format!("Ends <t:{end_stamp}:R>"), &format!("<t:{end_stamp}:f>"), true)
+                        .field("Weapons", weapons.iter().cloned().reduce(|acc, e| format!("{acc}\n{e}")).unwrap_or_default().as_str(), false)
+                        .field("King Salmonid", king, false)
+                        .field("Stage", &stage.0, false)
+                        .image(&stage.1)
+                    )
+                ;
+            },
+            Notification::WatchedRotation{start, end, weapons, king, stage, watched, ..} => {
+                let start_stamp = start.timestamp();
+                let end_stamp = end.timestamp();
+                message
+                    .embed(|embed| self.prefix_embed(style, embed)
+                        .description(&format!("Watching: {}", watched.join(", ")))
+                        .field(&format!("Starts <t:{start_stamp}:R>"), &format!("<t:{start_stamp}:f>"), true)
+                        .field(&format!("Ends <t:{end_stamp}:R>"), &format!("<t:{end_stamp}:f>"), true)
+                        .field("Weapons", &weapons.join("\n"), false)
+                        .field("King Salmonid", king, false)
+                        .field("Stage", &stage.0, false)
+                        .image(&stage.1)
+                    )
+                ;
+            },
+            Notification::BigRun{start, end, king, stage} |
+            Notification::Golden{start, end, king, stage, ..} => {
+                let start_stamp = start.timestamp();
+                let end_stamp = end.timestamp();
+                message
+                    .embed(|embed| self.prefix_embed(style, embed)
+                        .field(&format!("Starts <t:{start_stamp}:R>"), &format!("<t:{start_stamp}:f>"), true)
+                        .field(&format!("Ends <t:{end_stamp}:R>"), &format!("<t:{end_stamp}:f>"), true)
+                        .field("King Salmonid", king, false)
+                        .field("Stage", &stage.0, false)
+                        .image(&stage.1)
+                        .image(&stage.1)
+                    )
+                ;
+            },
+            Notification::Correction{original, changes, image} => {
+                let start_stamp = original.start().timestamp();
+                let end_stamp = original.end().timestamp();
+                message
+                    .embed(|embed| {
+                        self.prefix_embed(style, embed)
+                            .description(&original.to_string())
+                            .field(&format!("Starts <t:{start_stamp}:R>"), &format!("<t:{start_stamp}:f>"), true)
+                            .field(&format!("Ends <t:{end_stamp}:R>"), &format!("<t:{end_stamp}:f>"), true)
+                        ;
+                        for change in changes {
+                            embed.field(change.field, &format!("~~{}~~\n{}", change.old, change.new), false);
+                        }
+                        embed.image(image)
+                    })
+                ;
+            },
+            Notification::Cancellation{original} => {
+                let start_stamp = original.start().timestamp();
+                message
+                    .embed(|embed| self.prefix_embed(style, embed)
+                        .description(&original.to_string())
+                        .field("Was scheduled for", &format!("<t:{start_stamp}:f>"), false)
+                    )
+                ;
+            },
+            Notification::Reminder{original, moment, at, ..} => {
+                // Same embed as the announcement, retitled
+                original.setup_message(style, message);
+                let at_stamp = at.timestamp();
+                if let Some(embed) = message.embeds.last_mut() {
+                    embed.title = Some(self.title().to_owned());
+                    embed.description = Some(format!("{original}\n{} <t:{at_stamp}:R>", match moment {
+                        Moment::Start => "Starts",
+                        Moment::Tricolor => "Tricolor Battles start",
+                        Moment::End => "Ends",
+                    }));
+                }
+            },
+        }
+        message
+    }
+}
+
+/// Escapes `&`, `<` and `>`, which mean markup in HTML and Slack's mrkdwn alike.
+///
+/// Quotes are left as they are: they only matter inside HTML attributes, and Slack would show `&quot;` as is.
+pub(crate) fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Escapes an iCalendar TEXT value, in which backslashes, commas, semicolons and newlines are special.
+pub(crate) fn escape_ical_text(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+/// Cuts `text` down to `limit` characters, ending in an ellipsis when it was too long.
+pub(crate) fn truncate(text: &str, limit: usize) -> String {
+    match text.char_indices().nth(limit.saturating_sub(1)) {
+        Some((index, _)) if text[index..].chars().count() > 1 => format!("{}…", &text[..index]),
+        _ => text.to_owned(),
+    }
+}
@@ -0,0 +1,192 @@
+use chrono::Utc;
+
+use crate::{
+    config::Config,
+    diff::{self, Change},
+    notification::{FieldChange, Notification},
+    schedule_data::{self, bigRunRotation, eggstraWorkRotation, salmonRunRotation},
+};
+
+/// The parts of a Salmon Run rotation a correction can report on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RotationDetails {
+    pub weapons: Vec<String>,
+    /// Name and image URL.
+    pub stage: (String, String),
+    pub king: Option<String>,
+}
+
+impl RotationDetails {
+    /// What differs in `new`, in the order the fields are shown.
+    pub fn changes(&self, new: &RotationDetails) -> Vec<FieldChange> {
+        let mut changes = vec![];
+        if self.weapons != new.weapons {
+            changes.push(FieldChange { field: "Weapons", old: self.weapons.join(", "), new: new.weapons.join(", ") });
+        }
+        if self.stage.0 != new.stage.0 {
+            changes.push(FieldChange { field: "Stage", old: self.stage.0.clone(), new: new.stage.0.clone() });
+        }
+        if self.king != new.king {
+            changes.push(FieldChange {
+                field: "King Salmonid",
+                old: self.king.clone().unwrap_or_default(),
+                new: new.king.clone().unwrap_or_default(),
+            });
+        }
+        changes
+    }
+}
+
+/// A Salmon Run rotation from any of the schedules.
+pub trait Rotation: diff::Identity + PartialEq + Clone {
+    fn details(&self) -> RotationDetails;
+    /// The announcement for this rotation, if it is worth one.
+    fn notification(&self) -> Option<Notification>;
+}
+
+impl Rotation for salmonRunRotation {
+    fn details(&self) -> RotationDetails {
+        RotationDetails {
+            weapons: self.setting.weapons.iter().map(|weapon| weapon.name.clone()).collect(),
+            stage: (self.setting.coopStage.name.clone(), self.setting.coopStage.image.url.clone()),
+            king: Some(self.__splatoon3ink_king_salmonid_guess.clone()),
+        }
+    }
+
+    fn notification(&self) -> Option<Notification> {
+        self.setting.weapons.iter().find(|weapon| is_golden_weapon(weapon)).map(|golden|
+            Notification::Golden {
+                start: self.startTime.to_utc(),
+                end: self.endTime.to_utc(),
+                king: self.__splatoon3ink_king_salmonid_guess.clone(),
+                stage: (
+                    self.setting.coopStage.name.clone(),
+                    self.setting.coopStage.image.url.clone()
+                ),
+                weapon_image: golden.image.url.clone(),
+            }
+        )
+        .or_else(|| self.setting.weapons.iter().any(is_random_weapon).then(||
+            Notification::Random {
+                start: self.startTime.to_utc(),
+                end: self.endTime.to_utc(),
+                weapons: self.setting.weapons.iter().cloned().map(|weapon| weapon.name).collect(),
+                king: self.__splatoon3ink_king_salmonid_guess.clone(),
+                stage: (
+                    self.setting.coopStage.name.clone(),
+                    self.setting.coopStage.image.url.clone()
+                )
+            }
+        ))
+    }
+}
+
+/// splatoon3.ink's ID for the green `Random` weapon.
+const RANDOM_WEAPON_ID: &str = "52e07029f01362a4";
+/// splatoon3.ink's ID for the golden `Random` weapon, only handed out in Golden rotations.
+const GOLDEN_WEAPON_ID: &str = "747937841598fff7";
+/// Image hash of the golden question mark, in case splatoon3.ink's ID for it changes.
+const GOLDEN_RANDOM_IMAGE: &str = "9d7272733ae2f2282938da17d69f13419a935eef42239132a02fcf37d8678f10";
+
+/// Either question mark.
+fn is_random_weapon(weapon: &schedule_data::weapon) -> bool {
+    weapon.__splatoon3ink_id.contains(RANDOM_WEAPON_ID) || is_golden_weapon(weapon) || weapon.name == "Random"
+}
+
+/// The golden question mark, any other one is taken for the green one.
+fn is_golden_weapon(weapon: &schedule_data::weapon) -> bool {
+    weapon.__splatoon3ink_id.contains(GOLDEN_WEAPON_ID) || weapon.image.url.contains(GOLDEN_RANDOM_IMAGE)
+}
+
+impl Rotation for bigRunRotation {
+    fn details(&self) -> RotationDetails {
+        RotationDetails {
+            weapons: self.setting.weapons.iter().map(|weapon| weapon.name.clone()).collect(),
+            stage: (self.setting.coopStage.name.clone(), self.setting.coopStage.image.url.clone()),
+            king: Some(self.__splatoon3ink_king_salmonid_guess.clone()),
+        }
+    }
+
+    fn notification(&self) -> Option<Notification> {
+        Some(Notification::BigRun {
+            start: self.startTime.to_utc(),
+            end: self.endTime.to_utc(),
+            king: self.__splatoon3ink_king_salmonid_guess.clone(),
+            stage: (
+                self.setting.coopStage.name.clone(),
+                self.setting.coopStage.image.url.clone()
+            )
+        })
+    }
+}
+
+impl Rotation for eggstraWorkRotation {
+    fn details(&self) -> RotationDetails {
+        RotationDetails {
+            weapons: self.setting.weapons.iter().map(|weapon| weapon.name.clone()).collect(),
+            stage: (self.setting.coopStage.name.clone(), self.setting.coopStage.image.url.clone()),
+            king: None,
+        }
+    }
+
+    fn notification(&self) -> Option<Notification> {
+        Some(Notification::EggstraWork {
+            start: self.startTime.to_utc(),
+            end: self.endTime.to_utc(),
+            weapons: self.setting.weapons.clone().map(|weapon| weapon.name),
+            stage: (
+                self.setting.coopStage.name.clone(),
+                self.setting.coopStage.image.url.clone(),
+            )
+        })
+    }
+}
+
+/// Announces new rotations, and corrects ones that were announced before splatoon3.ink revised them.
+pub fn rotation_notifications<T: Rotation>(config: &Config, file_nodes: &[T], internet_nodes: &[T]) -> impl Iterator<Item = Notification> {
+    let corrections = config.corrections.enabled;
+    diff::diff(file_nodes, internet_nodes).into_iter().filter_map(move |change| match change {
+        Change::Added(event) => event.notification(),
+        Change::Changed{old, new} => match old.notification() {
+            // Wasn't announced before, so this is the first time it's worth mentioning
+            None => new.notification(),
+            Some(original) => {
+                let changes = old.details().changes(&new.details());
+                (corrections && !changes.is_empty()).then(|| Notification::Correction {
+                    original: Box::new(original),
+                    changes,
+                    image: new.details().stage.1,
+                })
+            },
+        },
+        // splatoon3.ink only drops rotations once they're over, so one vanishing early was called off
+        Change::Removed(event) => event.notification()
+            .filter(|original| corrections && original.start() > Utc::now())
+            .map(|original| Notification::Cancellation { original: Box::new(original) }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{schedule_data::RotationData, NotificationKind};
+
+    #[test]
+    fn salmon_run_rotations_are_classified() {
+        let data: RotationData = serde_json::from_str(include_str!("../tests/fixtures/schedules_salmon_variants.json")).unwrap();
+        let notifications: Vec<_> = data.data.coopGroupingSchedule.regularSchedules.nodes.iter().map(Rotation::notification).collect();
+        assert!(matches!(notifications.as_slice(), [
+            None,
+            Some(Notification::Random{..}),
+            Some(Notification::Random{..}),
+            Some(Notification::Golden{..}),
+            // A question mark whose image isn't known is still a plain random one
+            Some(Notification::Random{..}),
+        ]));
+        let Some(golden) = &notifications[3] else { unreachable!() };
+        assert_eq!(golden.kind(), NotificationKind::Golden);
+        assert_eq!(golden.color(), Notification::COLOR_GOLDEN);
+        assert!(golden.thumbnail().is_some_and(|thumbnail| thumbnail.contains(GOLDEN_RANDOM_IMAGE)));
+    }
+}
+
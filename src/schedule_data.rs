@@ -6,6 +6,8 @@ use std::fmt::{Display, Debug};
 
 use crate::splatfest_data::Color;
 
+use serde_json::Value;
+
 use super::serde::{Deserialize, Serialize};
 use super::chrono::prelude::*;
 
@@ -27,7 +29,9 @@ pub struct data {
     pub festSchedules: nodes<festSchedule>,
     pub coopGroupingSchedule: coopGroupingSchedule,
     pub currentFest: Option<currentFest>,
-    pub currentPlayer: Player,
+    /// Only there for whoever's data splatoon3.ink was fetched with.
+    #[serde(default)]
+    pub currentPlayer: Option<Player>,
     pub vsStages: nodes<vsStageRecon>,
 }
 
@@ -222,7 +226,7 @@ pub struct currentFest {
 pub struct team {
     pub id: String,
     pub color: Color,
-    pub myVoteState: Option<()>,
+    pub myVoteState: Option<Value>,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -242,6 +246,6 @@ pub struct vsStageRecon {
     pub vsStageId: isize,
     pub originalImage: image,
     pub name: String,
-    pub stats: Option<()>,
+    pub stats: Option<Value>,
     pub id: String,
 }
\ No newline at end of file
@@ -0,0 +1,218 @@
+use std::{collections::{HashMap, HashSet}, fmt::Display};
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use serde_path_to_error::Segment;
+
+use crate::{diff::Identity, error::{Error, Result}};
+
+/// What [`parse`] had to leave out or didn't recognize.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    /// Nodes that didn't match the models, in document order.
+    pub skipped: Vec<Skipped>,
+    /// Paths of fields the models don't know.
+    pub unknown: Vec<String>,
+}
+
+/// A node of one of the `nodes` lists that was left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skipped {
+    /// The node itself, e.g. `data.xSchedules.nodes[3]`.
+    pub node: String,
+    /// Where inside it the models broke.
+    pub path: String,
+    pub error: String,
+}
+
+impl Display for Skipped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} (skipped {})", self.path, self.error, self.node)
+    }
+}
+
+impl Report {
+    /// Whether every node matched the models.
+    pub fn is_clean(&self) -> bool {
+        self.skipped.is_empty()
+    }
+
+    /// Whether a node of the `nodes` list at `list`, e.g. `data.xSchedules.nodes`, was skipped.
+    pub fn skipped_from(&self, list: &str) -> bool {
+        self.skipped.iter().any(|skipped| skipped.node.strip_prefix(list).is_some_and(|rest| rest.starts_with('[')))
+    }
+
+    /// Puts the entries of `previous` that `fresh` lacks back into it when a node was skipped from `list`.
+    ///
+    /// Any of them may be the skipped node, which would otherwise look removed and go missing from the committed copy.
+    /// The ones that have `ended` are left out, so the list doesn't grow for as long as the node stays broken.
+    pub fn keep_skipped<T: Identity + Clone>(&self, list: &str, previous: &[T], fresh: &mut Vec<T>, ended: impl Fn(&T) -> bool) {
+        if !self.skipped_from(list) {
+            return;
+        }
+        let ids: HashSet<_> = fresh.iter().map(Identity::identity).collect();
+        let missing: Vec<_> = previous.iter().filter(|entry| !ids.contains(&entry.identity()) && !ended(entry)).cloned().collect();
+        fresh.extend(missing);
+    }
+
+    /// Logs the skipped nodes of `source`, unknown fields only by count since they are usually harmless.
+    pub fn log(&self, source: &str) {
+        for skipped in &self.skipped {
+            eprintln!("Schema Err in {source}: {skipped}");
+        }
+        if !self.unknown.is_empty() {
+            eprintln!("{source} has {} fields the models don't know, `validate` lists them", self.unknown.len());
+        }
+    }
+}
+
+/// Parses a splatoon3.ink document, leaving out every node of a `nodes` list that doesn't match the models.
+///
+/// One rotation or Splatfest splatoon3.ink changed the shape of only costs that node, a document broken anywhere else is an error.
+pub fn parse<T: DeserializeOwned>(json: &str) -> Result<(T, Report)> {
+    let mut value: Value = serde_json::from_str(json)?;
+    let mut report = Report::default();
+    // Indices in `json` of the nodes removed so far, by the path of their list
+    let mut removed: HashMap<String, Vec<usize>> = HashMap::new();
+    loop {
+        let mut unknown = vec![];
+        let mut ignored = |path: serde_ignored::Path| unknown.push(ignored_path(&path));
+        let deserializer = serde_ignored::Deserializer::new(&value, &mut ignored);
+        let err = match serde_path_to_error::deserialize::<_, T>(deserializer) {
+            Ok(parsed) => {
+                report.unknown = unknown.iter().map(|path| original_path(path, &removed)).collect();
+                return Ok((parsed, report));
+            },
+            Err(err) => err,
+        };
+        let segments: Vec<_> = err.path().iter().cloned().collect();
+        let node = segments.windows(2).rposition(|pair| matches!(pair, [Segment::Map { key }, Segment::Seq { .. }] if key == "nodes"));
+        let Some((list, Segment::Seq { index })) = node.map(|at| (&segments[..=at], &segments[at + 1])) else {
+            return Err(Error::Schema(err.path().to_string(), err.into_inner()));
+        };
+        let Some(Value::Array(nodes)) = lookup(&mut value, list) else {
+            return Err(Error::Schema(err.path().to_string(), err.into_inner()));
+        };
+        nodes.remove(*index);
+        let list = path_string(list);
+        let path = original_path(&err.path().to_string(), &removed);
+        let indices = removed.entry(list.clone()).or_default();
+        let index = original_index(indices, *index);
+        indices.push(index);
+        indices.sort_unstable();
+        report.skipped.push(Skipped { node: format!("{list}[{index}]"), path, error: err.into_inner().to_string() });
+    }
+}
+
+/// Where the node now at `index` was before the nodes at `removed` were taken out.
+fn original_index(removed: &[usize], index: usize) -> usize {
+    removed.iter().fold(index, |index, &gone| if gone <= index { index + 1 } else { index })
+}
+
+/// `path` with the index into every list nodes were removed from put back to what it was in the document.
+fn original_path(path: &str, removed: &HashMap<String, Vec<usize>>) -> String {
+    for (list, indices) in removed {
+        let Some(rest) = path.strip_prefix(list.as_str()).and_then(|rest| rest.strip_prefix('[')) else {
+            continue;
+        };
+        let Some((index, rest)) = rest.split_once(']').and_then(|(index, rest)| Some((index.parse().ok()?, rest))) else {
+            continue;
+        };
+        return format!("{list}[{}]{rest}", original_index(indices, index));
+    }
+    path.to_owned()
+}
+
+fn lookup<'a>(value: &'a mut Value, path: &[Segment]) -> Option<&'a mut Value> {
+    path.iter().try_fold(value, |value, segment| match segment {
+        Segment::Map { key } => value.get_mut(key),
+        Segment::Seq { index } => value.get_mut(index),
+        Segment::Enum { .. } | Segment::Unknown => None,
+    })
+}
+
+/// Formats `path` the way [`serde_path_to_error::Path`] does.
+fn path_string(path: &[Segment]) -> String {
+    path.iter().enumerate().map(|(at, segment)| match segment {
+        Segment::Seq { index } => format!("[{index}]"),
+        Segment::Map { key } if at == 0 => key.clone(),
+        Segment::Map { key } => format!(".{key}"),
+        Segment::Enum { variant } => format!(".{variant}"),
+        Segment::Unknown => ".?".to_owned(),
+    })
+    .collect()
+}
+
+fn ignored_path(path: &serde_ignored::Path) -> String {
+    match path {
+        serde_ignored::Path::Root => String::new(),
+        serde_ignored::Path::Seq { parent, index } => format!("{}[{index}]", ignored_path(parent)),
+        serde_ignored::Path::Map { parent, key } => match ignored_path(parent) {
+            parent if parent.is_empty() => key.clone(),
+            parent => format!("{parent}.{key}"),
+        },
+        serde_ignored::Path::Some { parent } |
+        serde_ignored::Path::NewtypeStruct { parent } |
+        serde_ignored::Path::NewtypeVariant { parent } => ignored_path(parent),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule_data::RotationData;
+
+    const SCHEDULES: &str = include_str!("../tests/fixtures/schedules_new.json");
+
+    fn salmon_nodes(value: &mut Value) -> &mut Vec<Value> {
+        value["data"]["coopGroupingSchedule"]["regularSchedules"]["nodes"].as_array_mut().unwrap()
+    }
+
+    #[test]
+    fn broken_nodes_are_skipped_and_reported_at_their_index() {
+        let mut value: Value = serde_json::from_str(SCHEDULES).unwrap();
+        let nodes = salmon_nodes(&mut value);
+        let count = nodes.len();
+        nodes[0]["setting"]["weapons"].as_array_mut().unwrap().pop();
+        nodes[1]["startTime"] = Value::from("soon");
+        nodes[2]["mystery"] = Value::from(true);
+
+        let (data, report) = parse::<RotationData>(&value.to_string()).unwrap();
+        assert_eq!(data.data.coopGroupingSchedule.regularSchedules.nodes.len(), count - 2);
+        let list = "data.coopGroupingSchedule.regularSchedules.nodes";
+        let paths: Vec<_> = report.skipped.iter().map(|skipped| (skipped.node.as_str(), skipped.path.as_str())).collect();
+        assert_eq!(paths, [
+            (format!("{list}[0]").as_str(), format!("{list}[0].setting.weapons").as_str()),
+            (format!("{list}[1]").as_str(), format!("{list}[1].startTime").as_str()),
+        ]);
+        assert!(report.unknown.contains(&format!("{list}[2].mystery")));
+    }
+
+    #[test]
+    fn skipped_nodes_are_kept_from_the_previous_copy_until_they_end() {
+        let previous: RotationData = serde_json::from_str(SCHEDULES).unwrap();
+        let mut value: Value = serde_json::from_str(SCHEDULES).unwrap();
+        salmon_nodes(&mut value)[1]["startTime"] = Value::from("soon");
+        let (mut fresh, report) = parse::<RotationData>(&value.to_string()).unwrap();
+        let list = "data.coopGroupingSchedule.regularSchedules.nodes";
+        assert!(report.skipped_from(list) && !report.skipped_from("data.xSchedules.nodes"));
+
+        let (old, new) = (&previous.data.coopGroupingSchedule.regularSchedules.nodes, &mut fresh.data.coopGroupingSchedule.regularSchedules.nodes);
+        let mut ended = new.clone();
+        report.keep_skipped(list, old, &mut ended, |_| true);
+        assert_eq!(ended.len(), old.len() - 1);
+        report.keep_skipped(list, old, new, |_| false);
+        assert_eq!(new.len(), old.len());
+        assert!(new.contains(&old[1]));
+    }
+
+    #[test]
+    fn documents_broken_outside_a_node_fail() {
+        let mut value: Value = serde_json::from_str(SCHEDULES).unwrap();
+        value["data"]["coopGroupingSchedule"]["regularSchedules"] = Value::Null;
+        match parse::<RotationData>(&value.to_string()) {
+            Err(Error::Schema(path, _)) => assert_eq!(path, "data.coopGroupingSchedule.regularSchedules"),
+            other => panic!("expected a schema error, got {other:?}"),
+        }
+    }
+}
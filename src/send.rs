@@ -0,0 +1,301 @@
+use std::{collections::HashMap, future::Future, sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use reqwest::{Body, Client, RequestBuilder, Response, Url};
+use serde::Deserialize;
+use webhook::models::Message;
+
+use crate::{
+    config::{Config, Destination, SendingConfig},
+    error::{DiscordError, Error, ErrorClass, Result},
+    notification::{Notification, NotificationKind},
+    ratelimit::RateLimits,
+    sink::Sink,
+    state::State,
+};
+
+/// Seconds waited after a `429` that didn't say for how long.
+const DEFAULT_RETRY_AFTER: f64 = 1.0;
+
+/// Delivers rendered messages to a destination, `M` being what the matching [`Renderer`](crate::render::Renderer) produces.
+pub trait Sender<M> {
+    /// Posts `message` and returns the ID it was assigned, if the backend hands one out.
+    fn post(&self, destination: &Destination, message: &M) -> impl Future<Output = Result<Option<String>>>;
+    /// Replaces a previously posted message.
+    fn edit(&self, destination: &Destination, message_id: &str, message: &M) -> impl Future<Output = Result<()>>;
+    fn delete(&self, destination: &Destination, message_id: &str) -> impl Future<Output = Result<()>>;
+    /// Called once a run's deliveries are done, for senders that keep anything between sends.
+    fn end_run(&self) {}
+}
+
+/// What happened to the receiving side of a single delivery.
+#[derive(Debug)]
+pub enum Outcome {
+    /// Carries the message ID for backends that can edit or delete later.
+    Posted(Option<String>),
+    Edited,
+    Deleted,
+}
+
+/// One notification sent to one destination.
+#[derive(Debug)]
+pub struct Delivery<'a> {
+    pub destination: &'a str,
+    /// [`Notification::key`] of what was sent.
+    pub key: String,
+    pub kind: NotificationKind,
+    /// [`Notification::variant`] of what was sent.
+    pub variant: String,
+    /// Whether it was a reminder, which are remembered apart from announcements.
+    pub reminder: bool,
+    /// End of the event it was about, after which nothing more is sent about it.
+    pub until: DateTime<Utc>,
+    pub result: Result<Outcome>,
+    pub retries: Retries,
+}
+
+/// Rate limits and transient failures a delivery ran into before it went through or failed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Retries {
+    /// Rate limited attempts.
+    pub count: u32,
+    /// Attempts that failed with a server or connection error.
+    pub errors: u32,
+    /// Time spent waiting out `retry_after` and backoffs.
+    pub slept: Duration,
+}
+
+/// Sends every notification to every destination that accepts it.
+///
+/// Each webhook gets its own queue, sent in order of the events' start times; the queues run concurrently.
+/// Destinations disabled in `state` are left out, as is whatever `state` says was already delivered,
+/// and a queue stops sending once its webhook turns out to be gone.
+pub async fn send_notifications<'a, K: Sink>(sink: &K, config: &'a Config, state: &State, notifications: &[Notification]) -> Vec<Delivery<'a>> {
+    let enabled: Vec<_> = config.destinations.iter().filter(|destination| state.disabled_reason(destination).is_none()).collect();
+    let mut deliveries: Vec<_> = notifications.iter().flat_map(|notif|
+        enabled.iter().filter(|destination| destination.accepts(notif)).map(move |&destination| (notif, destination))
+    )
+    .filter(|(notif, destination)| !matches!(notif, Notification::Reminder{..}) || !state.was_reminded(&destination.name, &notif.key()))
+    // Left over from a run that failed for some other destination
+    .filter(|(notif, destination)| !state.was_delivered(&destination.name, notif.kind(), &notif.variant(), &notif.key()))
+    .collect();
+    deliveries.sort_by_key(|(notif, _)| notif.start());
+    let mut queues: Vec<Vec<_>> = vec![];
+    let mut queue_of_webhook = HashMap::new();
+    for (notif, destination) in deliveries {
+        let index = *queue_of_webhook.entry(destination.webhook_url.as_str()).or_insert_with(|| {
+            queues.push(vec![]);
+            queues.len() - 1
+        });
+        queues[index].push((notif, destination));
+    }
+    let sent: Vec<Vec<_>> = collect_futures(queues.into_iter().map(|queue| async move {
+        let mut sent = Vec::with_capacity(queue.len());
+        let mut gone = false;
+        for (notif, destination) in queue {
+            let delivery = if gone {
+                Delivery {
+                    destination: destination.name.as_str(),
+                    key: notif.key(),
+                    kind: notif.kind(),
+                    variant: notif.variant(),
+                    reminder: matches!(notif, Notification::Reminder{..}),
+                    until: notif.end(),
+                    result: Err(Error::Disabled(destination.name.clone())),
+                    retries: Retries::default(),
+                }
+            } else {
+                deliver(sink, config, state, notif, destination).await
+            };
+            gone |= delivery.result.as_ref().is_err_and(Error::disables_destination);
+            sent.push(delivery);
+        }
+        sent
+    }))
+    .await;
+    sent.into_iter().flatten().collect()
+}
+
+async fn deliver<'a, K: Sink>(sink: &K, config: &'a Config, state: &State, notif: &Notification, destination: &'a Destination) -> Delivery<'a> {
+    let style = destination.style(config, notif.kind());
+    let key = notif.key();
+    let sending = &config.sending;
+    println!("{notif} -> {}", destination.name);
+    let mut retries = Retries::default();
+    let result = match (notif, state.message_id(&destination.name, &key)) {
+        (Notification::Cancellation{..}, Some(message_id)) =>
+            retry_transient(sending, &mut retries, || sink.delete(destination, message_id)).await.map(|()| Outcome::Deleted),
+        (Notification::Correction{..}, Some(message_id)) if config.corrections.edit_original =>
+            retry_transient(sending, &mut retries, || sink.edit(destination, message_id, notif, &style)).await.map(|()| Outcome::Edited),
+        _ => retry_transient(sending, &mut retries, || sink.post(destination, notif, &style)).await.map(Outcome::Posted),
+    }
+    .inspect_err(|err| if err.disables_destination() {
+        eprintln!("Destination `{}` disabled, its webhook is gone or unauthorized: {err}", destination.name);
+    } else {
+        eprintln!("Sending Err ({}): {err}", destination.name);
+    });
+    Delivery {
+        destination: destination.name.as_str(),
+        key,
+        kind: notif.kind(),
+        variant: notif.variant(),
+        reminder: matches!(notif, Notification::Reminder{..}),
+        until: notif.end(),
+        result,
+        retries,
+    }
+}
+
+/// Repeats `request` while it fails with a [rate limit or transient error](ErrorClass), up to [`SendingConfig::max_retries`] times.
+///
+/// Rate limits are waited out for as long as Discord asks, other errors back off exponentially from [`SendingConfig::backoff_ms`].
+async fn retry_transient<T, F, Fut>(sending: &SendingConfig, retries: &mut Retries, mut request: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    loop {
+        match request().await {
+            Err(err) if retries.count + retries.errors < sending.max_retries => {
+                let wait = match (err.class(), &err) {
+                    (ErrorClass::RateLimited, Error::Discord(discord)) => {
+                        retries.count += 1;
+                        Duration::from_secs_f64(discord.retry_after.unwrap_or(DEFAULT_RETRY_AFTER))
+                    },
+                    (ErrorClass::RateLimited, _) => {
+                        retries.count += 1;
+                        Duration::from_secs_f64(DEFAULT_RETRY_AFTER)
+                    },
+                    (ErrorClass::Retryable, _) => {
+                        retries.errors += 1;
+                        Duration::from_millis(sending.backoff_ms.saturating_mul(1 << (retries.errors - 1).min(16)))
+                    },
+                    (ErrorClass::Permanent, _) => break Err(err),
+                };
+                retries.slept += wait;
+                async_std::task::sleep(wait).await;
+            },
+            res => break res,
+        }
+    }
+}
+
+async fn collect_futures<O, I>(iter: I) -> O
+where
+    I: IntoIterator,
+    I::Item: Future,
+    O: Default + Extend<<I::Item as Future>::Output>,
+{
+    let mut futures: Vec<_> = iter.into_iter().map(Box::pin).collect();
+    let mut results = O::default();
+    while !futures.is_empty() {
+        let (res, _, remaining) = futures::future::select_all(futures).await;
+        results.extend(std::iter::once(res));
+        futures = remaining;
+    }
+    results
+}
+
+/// Discord's reply to a webhook execution made with `?wait=true`.
+#[derive(Deserialize, Debug)]
+struct SentMessage {
+    id: String,
+}
+
+/// A Discord webhook, executed with `?wait=true` so the message ID comes back.
+#[derive(Debug, Clone, Default)]
+pub struct DiscordWebhook {
+    pub client: Client,
+    /// Shared by clones, so every request to a bucket draws from the same budget.
+    pub limits: Arc<RateLimits>,
+}
+
+impl DiscordWebhook {
+    pub fn new(client: Client) -> Self {
+        Self { client, limits: Default::default() }
+    }
+
+    /// Sends `request` once its bucket allows it and learns the limits from the response.
+    async fn execute(&self, route: &str, request: RequestBuilder) -> Result<Response> {
+        self.limits.acquire(route).await;
+        let response = request.send().await?;
+        self.limits.update(route, response.headers());
+        match check_response(response).await {
+            Err(Error::Discord(err)) if err.class() == ErrorClass::RateLimited => {
+                self.limits.limited(route, Duration::from_secs_f64(err.retry_after.unwrap_or(DEFAULT_RETRY_AFTER)), err.global);
+                Err(Error::Discord(err))
+            },
+            res => res,
+        }
+    }
+}
+
+impl Sender<Message> for DiscordWebhook {
+    async fn post(&self, destination: &Destination, message: &Message) -> Result<Option<String>> {
+        let body = serde_json::to_string(message)?;
+        let request = self.client
+            .post(&destination.webhook_url)
+            .query(&[("wait", "true")])
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+        ;
+        let response = self.execute(&destination.webhook_url, request).await?;
+        let sent: SentMessage = serde_json::from_str(&response.text().await?)?;
+        Ok(Some(sent.id))
+    }
+
+    async fn edit(&self, destination: &Destination, message_id: &str, message: &Message) -> Result<()> {
+        let body = serde_json::to_string(message)?;
+        let request = self.client
+            .patch(message_url(&destination.webhook_url, message_id)?)
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+        ;
+        self.execute(&message_route(destination), request).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, destination: &Destination, message_id: &str) -> Result<()> {
+        let request = self.client.delete(message_url(&destination.webhook_url, message_id)?);
+        self.execute(&message_route(destination), request).await?;
+        Ok(())
+    }
+}
+
+/// Edits and deletes share a bucket per webhook, apart from the one for new messages.
+fn message_route(destination: &Destination) -> String {
+    format!("{}/messages", destination.webhook_url)
+}
+
+/// `/webhooks/{id}/{token}/messages/{message_id}`, keeping any query the webhook URL already has.
+fn message_url(webhook_url: &str, message_id: &str) -> Result<Url> {
+    let mut url = Url::parse(webhook_url).map_err(|err| Error::Config(format!("invalid webhook URL: {err}")))?;
+    url.path_segments_mut()
+        .map_err(|()| Error::Config(format!("webhook URL can't have a message path: {webhook_url}")))?
+        .pop_if_empty()
+        .push("messages")
+        .push(message_id)
+    ;
+    Ok(url)
+}
+
+async fn check_response(response: Response) -> Result<Response> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        let status = response.status();
+        let body_bytes = response.bytes().await?;
+        let err_msg = String::from_utf8_lossy(&body_bytes);
+        Err(Error::Discord(DiscordError::new(status, &err_msg)))
+    }
+}
+
+/// Error check for backends other than Discord, which have no common error body.
+pub(crate) async fn check_status(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        Err(Error::Http(status, response.text().await?))
+    }
+}
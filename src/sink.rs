@@ -0,0 +1,137 @@
+use std::future::Future;
+
+use reqwest::Client;
+use serde::Deserialize;
+
+use crate::{
+    config::{Destination, KindStyle},
+    error::Result,
+    json::{JsonRenderer, JsonWebhook},
+    matrix::{MatrixRenderer, MatrixRoom},
+    notification::Notification,
+    render::{DiscordRenderer, Renderer},
+    send::{DiscordWebhook, Sender},
+    slack::{SlackRenderer, SlackWebhook},
+};
+
+/// Renders a [`Notification`] into a backend's own payload and delivers it.
+pub trait Sink {
+    /// Posts `notification` and returns the message ID, if the backend hands one out.
+    fn post(&self, destination: &Destination, notification: &Notification, style: &KindStyle) -> impl Future<Output = Result<Option<String>>>;
+    /// Replaces a previously posted message with `notification`.
+    fn edit(&self, destination: &Destination, message_id: &str, notification: &Notification, style: &KindStyle) -> impl Future<Output = Result<()>>;
+    fn delete(&self, destination: &Destination, message_id: &str) -> impl Future<Output = Result<()>>;
+    /// Posts a plain text message for whoever runs the bot.
+    fn alert(&self, destination: &Destination, text: &str) -> impl Future<Output = Result<()>>;
+    /// Called once a run's deliveries are done, see [`Sender::end_run`].
+    fn end_run(&self) {}
+}
+
+/// A [`Renderer`] feeding a [`Sender`].
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline<R, S> {
+    pub renderer: R,
+    pub sender: S,
+}
+
+impl<R: Renderer, S: Sender<R::Output>> Sink for Pipeline<R, S> {
+    async fn post(&self, destination: &Destination, notification: &Notification, style: &KindStyle) -> Result<Option<String>> {
+        self.sender.post(destination, &self.renderer.render(notification, style)).await
+    }
+
+    async fn edit(&self, destination: &Destination, message_id: &str, notification: &Notification, style: &KindStyle) -> Result<()> {
+        self.sender.edit(destination, message_id, &self.renderer.render(notification, style)).await
+    }
+
+    async fn delete(&self, destination: &Destination, message_id: &str) -> Result<()> {
+        self.sender.delete(destination, message_id).await
+    }
+
+    async fn alert(&self, destination: &Destination, text: &str) -> Result<()> {
+        self.sender.post(destination, &self.renderer.render_alert(text)).await?;
+        Ok(())
+    }
+
+    fn end_run(&self) {
+        self.sender.end_run();
+    }
+}
+
+/// Which built-in [`Sink`] a destination is delivered through.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    #[default]
+    Discord,
+    /// Slack incoming webhook with Block Kit layout.
+    Slack,
+    /// Matrix client-server API, `webhook_url` being the room's `/_matrix/client/v3/rooms/{roomId}` URL.
+    Matrix,
+    /// The notification as plain JSON, for anything else.
+    Json,
+}
+
+/// Every built-in backend, picked per destination by its [`Backend`].
+#[derive(Debug, Clone, Default)]
+pub struct Sinks {
+    pub discord: Pipeline<DiscordRenderer, DiscordWebhook>,
+    pub slack: Pipeline<SlackRenderer, SlackWebhook>,
+    pub matrix: Pipeline<MatrixRenderer, MatrixRoom>,
+    pub json: Pipeline<JsonRenderer, JsonWebhook>,
+}
+
+impl Sinks {
+    pub fn new(client: Client) -> Self {
+        Self {
+            discord: Pipeline { renderer: DiscordRenderer, sender: DiscordWebhook::new(client.clone()) },
+            slack: Pipeline { renderer: SlackRenderer, sender: SlackWebhook { client: client.clone() } },
+            matrix: Pipeline { renderer: MatrixRenderer, sender: MatrixRoom::new(client.clone()) },
+            json: Pipeline { renderer: JsonRenderer, sender: JsonWebhook { client } },
+        }
+    }
+}
+
+impl Sink for Sinks {
+    async fn post(&self, destination: &Destination, notification: &Notification, style: &KindStyle) -> Result<Option<String>> {
+        match destination.backend {
+            Backend::Discord => self.discord.post(destination, notification, style).await,
+            Backend::Slack => self.slack.post(destination, notification, style).await,
+            Backend::Matrix => self.matrix.post(destination, notification, style).await,
+            Backend::Json => self.json.post(destination, notification, style).await,
+        }
+    }
+
+    async fn edit(&self, destination: &Destination, message_id: &str, notification: &Notification, style: &KindStyle) -> Result<()> {
+        match destination.backend {
+            Backend::Discord => self.discord.edit(destination, message_id, notification, style).await,
+            Backend::Slack => self.slack.edit(destination, message_id, notification, style).await,
+            Backend::Matrix => self.matrix.edit(destination, message_id, notification, style).await,
+            Backend::Json => self.json.edit(destination, message_id, notification, style).await,
+        }
+    }
+
+    async fn delete(&self, destination: &Destination, message_id: &str) -> Result<()> {
+        match destination.backend {
+            Backend::Discord => self.discord.delete(destination, message_id).await,
+            Backend::Slack => self.slack.delete(destination, message_id).await,
+            Backend::Matrix => self.matrix.delete(destination, message_id).await,
+            Backend::Json => self.json.delete(destination, message_id).await,
+        }
+    }
+
+    async fn alert(&self, destination: &Destination, text: &str) -> Result<()> {
+        match destination.backend {
+            Backend::Discord => self.discord.alert(destination, text).await,
+            Backend::Slack => self.slack.alert(destination, text).await,
+            Backend::Matrix => self.matrix.alert(destination, text).await,
+            Backend::Json => self.json.alert(destination, text).await,
+        }
+    }
+
+    fn end_run(&self) {
+        self.discord.end_run();
+        self.slack.end_run();
+        self.matrix.end_run();
+        self.json.end_run();
+    }
+}
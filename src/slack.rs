@@ -0,0 +1,79 @@
+use reqwest::Client;
+use serde_json::{json, Value};
+
+use crate::{
+    config::{Destination, KindStyle},
+    error::{Error, Result},
+    notification::{format_time, Notification},
+    render::{escape_markup, truncate, Renderer},
+    send::{check_status, Sender},
+};
+
+/// Block Kit limits the header to 150 characters and section fields to 10.
+const HEADER_LIMIT: usize = 150;
+const FIELD_COUNT_LIMIT: usize = 10;
+const TEXT_LIMIT: usize = 2000;
+
+/// A Block Kit message: header, summary with times, facts and the image.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SlackRenderer;
+
+impl Renderer for SlackRenderer {
+    type Output = Value;
+
+    fn render(&self, notification: &Notification, _style: &KindStyle) -> Value {
+        let summary = escape_markup(&notification.to_string());
+        let times = format!("Starts {}\nEnds {}", date(notification.start()), date(notification.end()));
+        let mut blocks = vec![
+            json!({ "type": "header", "text": { "type": "plain_text", "text": truncate(notification.title(), HEADER_LIMIT) } }),
+            json!({ "type": "section", "text": { "type": "mrkdwn", "text": format!("*{summary}*\n{times}") } }),
+        ];
+        let facts = notification.facts();
+        if !facts.is_empty() {
+            let fields: Vec<_> = facts.iter().take(FIELD_COUNT_LIMIT).map(|(name, value)| json!({
+                "type": "mrkdwn",
+                "text": truncate(&format!("*{name}*\n{}", escape_markup(value)), TEXT_LIMIT),
+            })).collect();
+            blocks.push(json!({ "type": "section", "fields": fields }));
+        }
+        if let Some(image) = notification.image() {
+            blocks.push(json!({ "type": "image", "image_url": image, "alt_text": notification.to_string() }));
+        }
+        json!({
+            "text": format!("{}: {notification}", notification.title()),
+            "blocks": blocks,
+        })
+    }
+
+    fn render_alert(&self, text: &str) -> Value {
+        json!({ "text": escape_markup(text) })
+    }
+}
+
+/// Shown in the reader's timezone, with a UTC fallback.
+fn date(time: chrono::DateTime<chrono::Utc>) -> String {
+    format!("<!date^{}^{{date_short_pretty}} {{time}}|{}>", time.timestamp(), format_time(time))
+}
+
+/// A Slack incoming webhook. These answer with a plain `ok`, so there is no message ID to edit or delete later.
+#[derive(Debug, Clone, Default)]
+pub struct SlackWebhook {
+    pub client: Client,
+}
+
+impl Sender<Value> for SlackWebhook {
+    async fn post(&self, destination: &Destination, message: &Value) -> Result<Option<String>> {
+        let response = self.client.post(&destination.webhook_url).json(message).send().await?;
+        check_status(response).await?;
+        Ok(None)
+    }
+
+    /// Posts the new version, incoming webhooks can't touch earlier messages.
+    async fn edit(&self, destination: &Destination, _message_id: &str, message: &Value) -> Result<()> {
+        self.post(destination, message).await.map(|_| ())
+    }
+
+    async fn delete(&self, destination: &Destination, _message_id: &str) -> Result<()> {
+        Err(Error::Config(format!("destination `{}` is a Slack webhook, which can't delete messages", destination.name)))
+    }
+}
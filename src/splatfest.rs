@@ -0,0 +1,46 @@
+use crate::{
+    notification::{Notification, TeamResult},
+    splatfest_data::{Region, RegionalFest},
+};
+
+/// Results show up once the fest is closed and every team has been scored.
+pub fn has_results(fest: &RegionalFest) -> bool {
+    fest.fest.state == "CLOSED" && fest.fest.teams.iter().all(|team| team.result.is_some())
+}
+
+pub fn splatfest_results_notification(RegionalFest { fest, regions }: &RegionalFest) -> Option<Notification> {
+    let teams = fest.teams.clone().map(|team| team.result.map(|result| TeamResult {
+        name: team.teamName,
+        color: team.color.to_rgb(),
+        result,
+    }));
+    let [Some(alpha), Some(bravo), Some(charlie)] = teams else {
+        return None;
+    };
+    Some(Notification::SplatfestResults {
+        title: fest.title.clone(),
+        image: fest.image.url.clone(),
+        start: fest.startTime.to_utc(),
+        end: fest.endTime.to_utc(),
+        teams: Box::new([alpha, bravo, charlie]),
+        regions: regions.clone(),
+    })
+}
+
+pub fn splatfest_notification(RegionalFest { fest, regions }: &RegionalFest) -> Notification {
+    Notification::Splatfest {
+        title: fest.title.clone(),
+        teams: fest.teams.clone().map(|team| team.teamName),
+        team_image: fest.image.url.clone(),
+        start: fest.startTime.to_utc(),
+        tricolor: fest.startTime.to_utc() + ((fest.endTime - fest.startTime) / 2),
+        end: fest.endTime.to_utc(),
+        regions: regions.clone(),
+    }
+}
+
+/// `US+JP` style shorthand used in keys.
+pub fn region_codes(regions: &[Region]) -> String {
+    regions.iter().map(|region| format!("{region:?}")).collect::<Vec<_>>().join("+")
+}
+
@@ -2,8 +2,12 @@
 #![allow(non_camel_case_types)]
 #![allow(unused)]
 
+use std::fmt::Display;
+
 use crate::schedule_data::{Player, image};
 
+use serde_json::Value;
+
 use super::serde::{Deserialize, Serialize};
 use super::chrono::{DateTime, Local};
 
@@ -15,6 +19,70 @@ pub struct SplatfestData {
     pub AP: region,
 }
 
+/// The regions splatoon3.ink publishes Splatfests for, named like the fields of [`SplatfestData`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum Region {
+    US,
+    EU,
+    JP,
+    AP,
+}
+
+impl Region {
+    pub const ALL: [Region; 4] = [Region::US, Region::EU, Region::JP, Region::AP];
+}
+
+impl Display for Region {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Region::US => write!(f, "the Americas and Oceania"),
+            Region::EU => write!(f, "Europe"),
+            Region::JP => write!(f, "Japan"),
+            Region::AP => write!(f, "Hong Kong and Korea"),
+        }
+    }
+}
+
+/// A fest along with every region it is held in.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RegionalFest {
+    /// The fest as listed in the first of `regions`.
+    pub fest: splatfest,
+    pub regions: Vec<Region>,
+}
+
+impl RegionalFest {
+    /// Held everywhere rather than in some regions only.
+    pub fn is_global(&self) -> bool {
+        self.regions.len() == Region::ALL.len()
+    }
+}
+
+impl SplatfestData {
+    pub fn region(&self, region: Region) -> &region {
+        match region {
+            Region::US => &self.US,
+            Region::EU => &self.EU,
+            Region::JP => &self.JP,
+            Region::AP => &self.AP,
+        }
+    }
+
+    /// Every fest across all regions, with global fests merged by `__splatoon3ink_id`.
+    pub fn fests(&self) -> Vec<RegionalFest> {
+        let mut fests: Vec<RegionalFest> = vec![];
+        for region in Region::ALL {
+            for fest in &self.region(region).data.festRecords.nodes {
+                match fests.iter_mut().find(|known| known.fest.__splatoon3ink_id == fest.__splatoon3ink_id) {
+                    Some(known) => known.regions.push(region),
+                    None => fests.push(RegionalFest { fest: fest.clone(), regions: vec![region] }),
+                }
+            }
+        }
+        fests
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
 pub struct region {
     pub data: data,
@@ -23,7 +91,9 @@ pub struct region {
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
 pub struct data {
     pub festRecords: nodes,
-    pub currentPlayer: Player,
+    /// Only there for whoever's data splatoon3.ink was fetched with.
+    #[serde(default)]
+    pub currentPlayer: Option<Player>,
 }
 
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
@@ -41,9 +111,9 @@ pub struct splatfest {
     pub title: String,
     pub lang: String,
     pub image: image,
-    pub playerResult: Option<()>,
+    pub playerResult: Option<Value>,
     pub teams: [team;3],
-    pub myTeam: Option<()>,
+    pub myTeam: Option<Value>,
     pub __typename: String,
     pub isVotable: bool,
     pub undecidedVotes: Option<Votes>,
@@ -56,7 +126,7 @@ pub struct team {
     pub teamName: String,
     pub color: Color,
     pub image: image,
-    pub myVoteState: Option<()>,
+    pub myVoteState: Option<Value>,
     pub preVotes: Option<Votes>,
     pub votes: Option<Votes>,
     pub role: Option<String>
@@ -86,6 +156,14 @@ pub struct Color {
     pub b: f64,
 }
 
+impl Color {
+    /// Packs the 0-1 channels into `0xRRGGBB`, dropping alpha.
+    pub fn to_rgb(&self) -> u32 {
+        let channel = |value: f64| (value.clamp(0.0, 1.0) * 255.0).round() as u32;
+        (channel(self.r) << 16) | (channel(self.g) << 8) | channel(self.b)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct Votes {
     pub totalCount: usize,
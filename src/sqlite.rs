@@ -0,0 +1,205 @@
+use std::{fmt::Write, path::Path, sync::Mutex};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{
+    error::Result,
+    history::{HistoryQuery, Observation},
+    send::{Delivery, Outcome},
+    state::{self, State, StateStore},
+};
+
+/// Name of the database in the `[state]` `dir`.
+pub const DATABASE_PATH: &str = "splatink.sqlite3";
+
+/// Statements bringing the database from the `user_version` at each index to the next one.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE state (
+        id INTEGER PRIMARY KEY CHECK (id = 0),
+        document TEXT NOT NULL
+    );
+    CREATE TABLE caches (
+        name TEXT PRIMARY KEY,
+        document TEXT NOT NULL,
+        committed_at TEXT NOT NULL
+    );
+    CREATE TABLE observations (
+        kind TEXT NOT NULL,
+        identity TEXT NOT NULL,
+        start TEXT NOT NULL,
+        end TEXT NOT NULL,
+        data TEXT NOT NULL,
+        first_seen TEXT NOT NULL,
+        last_seen TEXT NOT NULL,
+        PRIMARY KEY (kind, identity)
+    );
+    CREATE TABLE deliveries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        attempted_at TEXT NOT NULL,
+        destination TEXT NOT NULL,
+        notification_key TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        status TEXT NOT NULL,
+        message_id TEXT,
+        error TEXT,
+        retries INTEGER NOT NULL
+    );
+    CREATE INDEX deliveries_by_destination ON deliveries (destination, attempted_at);",
+];
+
+/// State, caches and the full history of observed events and delivery attempts in one SQLite database.
+#[derive(Debug)]
+pub struct SqliteStore {
+    connection: Mutex<Connection>,
+}
+
+/// Timestamps are stored as RFC 3339 in UTC, which sorts the same as text.
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+impl SqliteStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut connection = Connection::open(path)?;
+        let version: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let transaction = connection.transaction()?;
+            transaction.execute_batch(migration)?;
+            transaction.pragma_update(None, "user_version", index + 1)?;
+            transaction.commit()?;
+        }
+        Ok(Self { connection: Mutex::new(connection) })
+    }
+
+    fn connection(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.connection.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// One line per matching row, tab separated.
+    pub fn history(&self, query: &HistoryQuery) -> Result<String> {
+        let connection = self.connection();
+        let mut out = String::new();
+        match query {
+            HistoryQuery::Observations { kind, limit } => {
+                let mut statement = connection.prepare(
+                    "SELECT start, end, kind, identity, first_seen, last_seen FROM observations
+                    WHERE ?1 IS NULL OR kind = ?1
+                    ORDER BY start DESC, kind LIMIT ?2",
+                )?;
+                let rows = statement.query_map(params![kind, *limit as i64], |row| {
+                    Ok([row.get::<_, String>(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?])
+                })?;
+                let _ = writeln!(out, "start\tend\tkind\tidentity\tfirst_seen\tlast_seen");
+                for row in rows {
+                    let _ = writeln!(out, "{}", row?.join("\t"));
+                }
+            },
+            HistoryQuery::Deliveries { destination, failed_only, limit } => {
+                let mut statement = connection.prepare(
+                    "SELECT attempted_at, destination, kind, notification_key, status, retries, coalesce(message_id, ''), coalesce(error, '') FROM deliveries
+                    WHERE (?1 IS NULL OR destination = ?1) AND (NOT ?2 OR status = 'failed')
+                    ORDER BY id DESC LIMIT ?3",
+                )?;
+                let rows = statement.query_map(params![destination, failed_only, *limit as i64], |row| {
+                    Ok([row.get::<_, String>(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get::<_, i64>(5)?.to_string(), row.get(6)?, row.get(7)?])
+                })?;
+                let _ = writeln!(out, "attempted_at\tdestination\tkind\tkey\tstatus\tretries\tmessage_id\terror");
+                for row in rows {
+                    let _ = writeln!(out, "{}", row?.join("\t"));
+                }
+            },
+        }
+        Ok(out)
+    }
+}
+
+impl StateStore for SqliteStore {
+    fn load(&self) -> Result<State> {
+        let document: Option<String> = self.connection()
+            .query_row("SELECT document FROM state WHERE id = 0", [], |row| row.get(0))
+            .optional()?
+        ;
+        match document {
+            Some(document) => state::migrate(serde_json::from_str(&document)?),
+            None => Ok(State::default()),
+        }
+    }
+
+    fn save(&self, state: &State) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO state (id, document) VALUES (0, ?1) ON CONFLICT (id) DO UPDATE SET document = excluded.document",
+            [serde_json::to_string(state)?],
+        )?;
+        Ok(())
+    }
+
+    fn cached(&self, name: &str) -> Result<Option<String>> {
+        Ok(self.connection().query_row("SELECT document FROM caches WHERE name = ?1", [name], |row| row.get(0)).optional()?)
+    }
+
+    fn commit_cache(&self, name: &str, json: &str) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO caches (name, document, committed_at) VALUES (?1, ?2, ?3)
+            ON CONFLICT (name) DO UPDATE SET document = excluded.document, committed_at = excluded.committed_at",
+            params![name, json, timestamp(Utc::now())],
+        )?;
+        Ok(())
+    }
+
+    fn record_observations(&self, observations: &[Observation]) -> Result<()> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        let now = timestamp(Utc::now());
+        {
+            let mut statement = transaction.prepare(
+                "INSERT INTO observations (kind, identity, start, end, data, first_seen, last_seen) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+                ON CONFLICT (kind, identity) DO UPDATE SET start = excluded.start, end = excluded.end, data = excluded.data, last_seen = excluded.last_seen",
+            )?;
+            for observation in observations {
+                statement.execute(params![
+                    observation.kind,
+                    observation.identity,
+                    timestamp(observation.start),
+                    timestamp(observation.end),
+                    observation.data.to_string(),
+                    now,
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn record_deliveries(&self, deliveries: &[Delivery]) -> Result<()> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        let now = timestamp(Utc::now());
+        {
+            let mut statement = transaction.prepare(
+                "INSERT INTO deliveries (attempted_at, destination, notification_key, kind, status, message_id, error, retries)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?;
+            for delivery in deliveries {
+                let (status, message_id, error) = match &delivery.result {
+                    Ok(Outcome::Posted(message_id)) => ("posted", message_id.clone(), None),
+                    Ok(Outcome::Edited) => ("edited", None, None),
+                    Ok(Outcome::Deleted) => ("deleted", None, None),
+                    Err(err) => ("failed", None, Some(err.to_string())),
+                };
+                statement.execute(params![
+                    now,
+                    delivery.destination,
+                    delivery.key,
+                    delivery.kind.to_string(),
+                    status,
+                    message_id,
+                    error,
+                    delivery.retries.count + delivery.retries.errors,
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+}
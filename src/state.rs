@@ -0,0 +1,531 @@
+use std::{collections::HashMap, fs::{self, File}, io::Write, path::{Path, PathBuf}, sync::Arc};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[cfg(feature = "sqlite")]
+use crate::sqlite::{SqliteStore, DATABASE_PATH};
+use crate::{config::Destination, error::{Error, Result}, fetch::Validators, history::{HistoryQuery, Observation}, send::Delivery, NotificationKind};
+
+pub const STATE_PATH: &str = "State Json.json";
+
+/// Version of the [`State`] layout written by this build.
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// Upgrades a state document from the version at each index to the next one.
+const MIGRATIONS: [fn(&mut Map<String, Value>); SCHEMA_VERSION as usize] = [
+    per_destination,
+    expiring_messages,
+    delivery_variants,
+];
+
+/// How long after its event started a message from before version 2, which didn't record the end, is kept.
+const LEGACY_MESSAGE_LIFETIME: TimeDelta = TimeDelta::days(7);
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateConfig {
+    /// Directory the state and the splatoon3.ink caches are kept in, created if missing.
+    pub dir: PathBuf,
+    pub backend: StoreBackend,
+}
+
+impl Default for StateConfig {
+    fn default() -> Self {
+        Self { dir: PathBuf::from("."), backend: StoreBackend::default() }
+    }
+}
+
+/// How the `[state]` `dir` is laid out.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StoreBackend {
+    /// A JSON file for the state and one per source.
+    #[default]
+    File,
+    /// A single database that also keeps every observed event and delivery attempt, needs the `sqlite` feature.
+    Sqlite,
+}
+
+/// Bookkeeping that has to outlive a single run.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct State {
+    pub version: u32,
+    /// Everything known about each destination, by name.
+    pub destinations: HashMap<String, DestinationState>,
+    /// Everything known about each source, by the name its copy is committed under.
+    pub sources: HashMap<String, SourceState>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self { version: SCHEMA_VERSION, destinations: HashMap::new(), sources: HashMap::new() }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DestinationState {
+    /// Messages by [`Notification::key`](crate::Notification::key).
+    pub messages: HashMap<String, Message>,
+    /// Notifications delivered since the caches were last committed, so a retried run doesn't send them twice.
+    pub delivered: HashMap<String, DateTime<Utc>>,
+    /// Reminders sent, by key, with the end of their event after which they are forgotten.
+    pub reminded: HashMap<String, DateTime<Utc>>,
+    /// Set once the webhook failed permanently.
+    pub disabled: Option<Disabled>,
+    /// Runs in a row in which it failed in a way a retry could fix, holding back the caches.
+    pub held_runs: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SourceState {
+    /// Of the committed copy.
+    #[serde(flatten)]
+    pub validators: Validators,
+    /// Since when the committed copy has been standing in for splatoon3.ink, unset while it answers.
+    pub stale_since: Option<DateTime<Utc>>,
+    /// Whether the alert about this went out already.
+    pub alerted: bool,
+}
+
+/// A posted announcement that follow-ups can edit or delete.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Message {
+    pub id: String,
+    /// End of the announced event, after which the message is forgotten.
+    pub until: DateTime<Utc>,
+}
+
+/// Why a destination is no longer sent to.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Disabled {
+    /// The URL that failed, a destination pointed at a new one is sent to again.
+    pub webhook_url: String,
+    pub reason: String,
+    pub since: DateTime<Utc>,
+}
+
+/// Where [`State`] and the splatoon3.ink documents it was diffed against are kept between runs.
+pub trait StateStore {
+    /// The saved state, or an empty one if nothing was saved yet.
+    fn load(&self) -> Result<State>;
+    fn save(&self, state: &State) -> Result<()>;
+    /// The source document last committed under `name`, if any.
+    fn cached(&self, name: &str) -> Result<Option<String>>;
+    /// Replaces the document under `name`, done once everything new in it was delivered.
+    fn commit_cache(&self, name: &str, json: &str) -> Result<()>;
+    /// Keeps the events of a fetched document, stores without history drop them.
+    fn record_observations(&self, _observations: &[Observation]) -> Result<()> {
+        Ok(())
+    }
+    /// Keeps a run's delivery attempts, stores without history drop them.
+    fn record_deliveries(&self, _deliveries: &[Delivery]) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A store shared with the [status server](crate::status::serve).
+impl<S: StateStore + ?Sized> StateStore for Arc<S> {
+    fn load(&self) -> Result<State> {
+        (**self).load()
+    }
+
+    fn save(&self, state: &State) -> Result<()> {
+        (**self).save(state)
+    }
+
+    fn cached(&self, name: &str) -> Result<Option<String>> {
+        (**self).cached(name)
+    }
+
+    fn commit_cache(&self, name: &str, json: &str) -> Result<()> {
+        (**self).commit_cache(name, json)
+    }
+
+    fn record_observations(&self, observations: &[Observation]) -> Result<()> {
+        (**self).record_observations(observations)
+    }
+
+    fn record_deliveries(&self, deliveries: &[Delivery]) -> Result<()> {
+        (**self).record_deliveries(deliveries)
+    }
+}
+
+/// The store picked by [`StateConfig::backend`].
+#[derive(Debug)]
+pub enum Store {
+    File(FileStore),
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqliteStore),
+}
+
+impl Store {
+    pub fn open(config: &StateConfig) -> Result<Self> {
+        let file = FileStore::open(&config.dir)?;
+        match config.backend {
+            StoreBackend::File => Ok(Self::File(file)),
+            #[cfg(feature = "sqlite")]
+            StoreBackend::Sqlite => Ok(Self::Sqlite(SqliteStore::open(&file.dir.join(DATABASE_PATH))?)),
+            #[cfg(not(feature = "sqlite"))]
+            StoreBackend::Sqlite => Err(Error::Config("[state] backend `sqlite` needs a build with the `sqlite` feature".to_owned())),
+        }
+    }
+
+    /// Answers `query` from the kept history, only the SQLite backend has one.
+    #[cfg_attr(not(feature = "sqlite"), allow(unused_variables))]
+    pub fn history(&self, query: &HistoryQuery) -> Result<String> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(store) => store.history(query),
+            Store::File(_) => Err(Error::Config("history is only kept with [state] backend = \"sqlite\"".to_owned())),
+        }
+    }
+}
+
+impl StateStore for Store {
+    fn load(&self) -> Result<State> {
+        match self {
+            Store::File(store) => store.load(),
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(store) => store.load(),
+        }
+    }
+
+    fn save(&self, state: &State) -> Result<()> {
+        match self {
+            Store::File(store) => store.save(state),
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(store) => store.save(state),
+        }
+    }
+
+    fn cached(&self, name: &str) -> Result<Option<String>> {
+        match self {
+            Store::File(store) => store.cached(name),
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(store) => store.cached(name),
+        }
+    }
+
+    fn commit_cache(&self, name: &str, json: &str) -> Result<()> {
+        match self {
+            Store::File(store) => store.commit_cache(name, json),
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(store) => store.commit_cache(name, json),
+        }
+    }
+
+    fn record_observations(&self, observations: &[Observation]) -> Result<()> {
+        match self {
+            Store::File(store) => store.record_observations(observations),
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(store) => store.record_observations(observations),
+        }
+    }
+
+    fn record_deliveries(&self, deliveries: &[Delivery]) -> Result<()> {
+        match self {
+            Store::File(store) => store.record_deliveries(deliveries),
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(store) => store.record_deliveries(deliveries),
+        }
+    }
+}
+
+/// JSON files in a directory, [`STATE_PATH`] and one per cached source.
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    pub dir: PathBuf,
+}
+
+impl FileStore {
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+}
+
+impl StateStore for FileStore {
+    fn load(&self) -> Result<State> {
+        let path = self.dir.join(STATE_PATH);
+        if !path.exists() {
+            return Ok(State::default());
+        }
+        migrate(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    fn save(&self, state: &State) -> Result<()> {
+        write_atomic(&self.dir.join(STATE_PATH), serde_json::to_string(state)?.as_bytes())
+    }
+
+    fn cached(&self, name: &str) -> Result<Option<String>> {
+        let path = self.dir.join(name);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(fs::read_to_string(path)?))
+    }
+
+    fn commit_cache(&self, name: &str, json: &str) -> Result<()> {
+        write_atomic(&self.dir.join(name), json.as_bytes())
+    }
+}
+
+/// Writes `contents` next to `path` and renames it over, so a crash leaves either the old or the new file.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Brings a saved state document of any earlier version up to [`SCHEMA_VERSION`].
+pub(crate) fn migrate(document: Value) -> Result<State> {
+    let Value::Object(mut document) = document else {
+        return Err(Error::Config("saved state isn't a JSON object".to_owned()));
+    };
+    // Documents from before versioning have no version field
+    let version = document.get("version").and_then(Value::as_u64).unwrap_or(0);
+    let Some(migrations) = usize::try_from(version).ok().and_then(|version| MIGRATIONS.get(version..)) else {
+        return Err(Error::Config(format!("saved state has schema version {version}, this build only knows up to {SCHEMA_VERSION}")));
+    };
+    for migration in migrations {
+        migration(&mut document);
+    }
+    document.insert("version".to_owned(), SCHEMA_VERSION.into());
+    Ok(serde_json::from_value(Value::Object(document))?)
+}
+
+/// Version 0 kept `messages` and `disabled` as separate maps by destination name.
+fn per_destination(document: &mut Map<String, Value>) {
+    let mut destinations = Map::new();
+    for field in ["messages", "disabled"] {
+        let Some(Value::Object(by_destination)) = document.remove(field) else {
+            continue;
+        };
+        for (name, value) in by_destination {
+            let destination = destinations.entry(name).or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(destination) = destination {
+                destination.insert(field.to_owned(), value);
+            }
+        }
+    }
+    document.insert("destinations".to_owned(), Value::Object(destinations));
+}
+
+/// Version 1 kept bare message IDs, including those of reminders, without knowing when they could go.
+fn expiring_messages(document: &mut Map<String, Value>) {
+    let Some(Value::Object(destinations)) = document.get_mut("destinations") else {
+        return;
+    };
+    let now = Utc::now();
+    for destination in destinations.values_mut() {
+        let Value::Object(destination) = destination else {
+            continue;
+        };
+        let Some(Value::Object(messages)) = destination.remove("messages") else {
+            continue;
+        };
+        let (mut kept, mut reminded) = (Map::new(), Map::new());
+        for (key, id) in messages {
+            // Keys carry their event's start time, the end wasn't kept
+            let start = key.split(':').find_map(|part| part.parse().ok()).and_then(|start| DateTime::from_timestamp(start, 0));
+            let until = Value::String((start.unwrap_or(now) + LEGACY_MESSAGE_LIFETIME).to_rfc3339());
+            if key.starts_with("reminder:") {
+                reminded.insert(key, until);
+            } else {
+                kept.insert(key, serde_json::json!({ "id": id, "until": until }));
+            }
+        }
+        destination.insert("messages".to_owned(), Value::Object(kept));
+        if let Value::Object(existing) = destination.entry("reminded").or_insert_with(|| Value::Object(Map::new())) {
+            existing.extend(reminded);
+        }
+    }
+}
+
+/// Version 3 tells deliveries apart by [`Notification::variant`](crate::Notification::variant), the ones from before are taken for announcements.
+fn delivery_variants(document: &mut Map<String, Value>) {
+    let Some(Value::Object(destinations)) = document.get_mut("destinations") else {
+        return;
+    };
+    for destination in destinations.values_mut() {
+        let Some(Value::Object(delivered)) = destination.get_mut("delivered") else {
+            continue;
+        };
+        *delivered = std::mem::take(delivered).into_iter()
+            .map(|(key, at)| match key.split_once('@') {
+                Some((kind, key)) => (format!("{kind}@announcement@{key}"), at),
+                None => (key, at),
+            })
+            .collect()
+        ;
+    }
+}
+
+impl State {
+    pub fn message_id(&self, destination: &str, key: &str) -> Option<&str> {
+        self.destinations.get(destination)?.messages.get(key).map(|message| message.id.as_str())
+    }
+
+    pub fn record_message(&mut self, destination: &str, key: String, id: String, until: DateTime<Utc>) {
+        self.destination(destination).messages.insert(key, Message { id, until });
+    }
+
+    pub fn forget_message(&mut self, destination: &str, key: &str) {
+        if let Some(destination) = self.destinations.get_mut(destination) {
+            destination.messages.remove(key);
+        }
+    }
+
+    pub fn was_delivered(&self, destination: &str, kind: NotificationKind, variant: &str, key: &str) -> bool {
+        self.destinations.get(destination).is_some_and(|destination| destination.delivered.contains_key(&delivery_key(kind, variant, key)))
+    }
+
+    pub fn record_delivery(&mut self, destination: &str, kind: NotificationKind, variant: &str, key: &str) {
+        self.destination(destination).delivered.insert(delivery_key(kind, variant, key), Utc::now());
+    }
+
+    pub fn was_reminded(&self, destination: &str, key: &str) -> bool {
+        self.destinations.get(destination).is_some_and(|destination| destination.reminded.contains_key(key))
+    }
+
+    pub fn record_reminder(&mut self, destination: &str, key: String, until: DateTime<Utc>) {
+        self.destination(destination).reminded.insert(key, until);
+    }
+
+    /// Drops what is only kept until an event ends for events that ended before `now`.
+    pub fn forget_ended(&mut self, now: DateTime<Utc>) {
+        for destination in self.destinations.values_mut() {
+            destination.messages.retain(|_, message| message.until > now);
+            destination.reminded.retain(|_, until| *until > now);
+        }
+    }
+
+    /// Forgets the delivery records of the kinds `of` picks, once the cache they guard against re-sending is committed.
+    pub fn clear_deliveries(&mut self, of: impl Fn(NotificationKind) -> bool) {
+        let prefixes: Vec<_> = NotificationKind::ALL.into_iter().filter(|kind| of(*kind)).map(|kind| format!("{kind}@")).collect();
+        for destination in self.destinations.values_mut() {
+            destination.delivered.retain(|key, _| !prefixes.iter().any(|prefix| key.starts_with(prefix)));
+        }
+    }
+
+    pub fn held_runs(&self, destination: &str) -> u32 {
+        self.destinations.get(destination).map_or(0, |destination| destination.held_runs)
+    }
+
+    /// Counts another run in a row `destination` was `failing` in, or starts over, returning the count.
+    pub fn record_held_run(&mut self, destination: &str, failing: bool) -> u32 {
+        if !failing {
+            if let Some(destination) = self.destinations.get_mut(destination) {
+                destination.held_runs = 0;
+            }
+            return 0;
+        }
+        let destination = self.destination(destination);
+        destination.held_runs += 1;
+        destination.held_runs
+    }
+
+    /// Why `destination` is disabled, as long as its webhook URL hasn't changed since.
+    pub fn disabled_reason(&self, destination: &Destination) -> Option<&str> {
+        self.destinations.get(&destination.name)?.disabled.as_ref()
+            .filter(|disabled| disabled.webhook_url == destination.webhook_url)
+            .map(|disabled| disabled.reason.as_str())
+    }
+
+    pub fn disable(&mut self, destination: &Destination, reason: String) {
+        self.destination(&destination.name).disabled = Some(Disabled {
+            webhook_url: destination.webhook_url.clone(),
+            reason,
+            since: Utc::now(),
+        });
+    }
+
+    fn destination(&mut self, name: &str) -> &mut DestinationState {
+        self.destinations.entry(name.to_owned()).or_default()
+    }
+}
+
+/// Corrections, cancellations and reminders share their original's kind and key, the [variant](crate::Notification::variant) tells them apart.
+///
+/// The kind comes first so [`State::clear_deliveries`] can drop a source's entries by prefix.
+fn delivery_key(kind: NotificationKind, variant: &str, key: &str) -> String {
+    format!("{kind}@{variant}@{key}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn unversioned_state_is_migrated_per_destination() {
+        let state = migrate(json!({
+            "messages": { "default": { "random:1": "1000" } },
+            "disabled": { "gone": { "webhook_url": "https://example.org", "reason": "404", "since": "2024-01-01T00:00:00Z" } },
+        })).unwrap();
+        assert_eq!(state.version, SCHEMA_VERSION);
+        assert_eq!(state.message_id("default", "random:1"), Some("1000"));
+        assert_eq!(state.destinations["gone"].disabled.as_ref().map(|disabled| disabled.reason.as_str()), Some("404"));
+
+        assert!(migrate(json!({ "version": SCHEMA_VERSION + 1 })).is_err());
+    }
+
+    #[test]
+    fn messages_are_given_an_end_and_dropped_after_it() {
+        let start = Utc::now() - TimeDelta::days(1);
+        let mut state = migrate(json!({
+            "version": 1,
+            "destinations": { "default": { "messages": {
+                format!("big_run:{}", start.timestamp()): "1000",
+                format!("reminder:big_run:{}:start:0", start.timestamp()): "1001",
+                "random:1": "1002",
+            } } },
+        })).unwrap();
+        let key = format!("big_run:{}", start.timestamp());
+        assert_eq!(state.message_id("default", &key), Some("1000"));
+        assert!(state.was_reminded("default", &format!("reminder:big_run:{}:start:0", start.timestamp())));
+        assert_eq!(state.destinations["default"].messages.len(), 2);
+
+        state.forget_ended(Utc::now());
+        assert_eq!(state.destinations["default"].messages.keys().collect::<Vec<_>>(), [&key]);
+        state.forget_ended(start + LEGACY_MESSAGE_LIFETIME + TimeDelta::seconds(1));
+        assert!(state.destinations["default"].messages.is_empty());
+        assert!(state.destinations["default"].reminded.is_empty());
+    }
+
+    #[test]
+    fn deliveries_from_before_variants_are_announcements() {
+        let state = migrate(json!({
+            "version": 2,
+            "destinations": { "default": { "delivered": { "random@random:1": "2024-07-10T00:00:00Z" } } },
+        })).unwrap();
+        assert!(state.was_delivered("default", NotificationKind::Random, "announcement", "random:1"));
+        assert!(!state.was_delivered("default", NotificationKind::Random, "cancellation", "random:1"));
+    }
+
+    #[test]
+    fn writes_replace_the_file_whole() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::open(dir.path().join("state")).unwrap();
+        let mut state = State::default();
+        state.record_delivery("default", NotificationKind::Random, "announcement", "random:1");
+        store.save(&state).unwrap();
+        store.commit_cache("cache.json", "{}").unwrap();
+
+        assert_eq!(store.load().unwrap(), state);
+        assert_eq!(store.cached("cache.json").unwrap().as_deref(), Some("{}"));
+        assert_eq!(store.cached("missing.json").unwrap(), None);
+        let files: Vec<_> = fs::read_dir(&store.dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        assert_eq!(files.len(), 2, "no temporary files left behind: {files:?}");
+    }
+}
@@ -0,0 +1,224 @@
+use std::{collections::BTreeMap, fmt::Write, net::SocketAddr, sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+use axum::{extract::State, http::{header, StatusCode}, response::{IntoResponse, Response}, routing::get, Json, Router};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::{net::TcpListener, sync::watch};
+
+use crate::{error::Result, fetch::{Conditional, Fetcher, Validators, SCHEDULES_CACHE}, send::Delivery, state::StateStore, NotificationKind};
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatusConfig {
+    /// Address the daemon serves `/healthz`, `/metrics` and `/state` on, no server when left out.
+    pub listen: Option<SocketAddr>,
+}
+
+/// Upper bounds of the fetch latency histogram, in seconds.
+const LATENCY_BUCKETS: [f64; 8] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// A source counts as unhealthy once this many polls in a row went without a successful fetch.
+const MISSED_POLLS: i32 = 3;
+
+#[derive(Debug, Default)]
+struct SourceStats {
+    last_success: Option<DateTime<Utc>>,
+    failures: u64,
+    /// Count per [`LATENCY_BUCKETS`] entry, not cumulative.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    sources: BTreeMap<&'static str, SourceStats>,
+    last_post: Option<DateTime<Utc>>,
+    sent: BTreeMap<NotificationKind, u64>,
+    failed: BTreeMap<NotificationKind, u64>,
+    rate_limited: u64,
+    retry_sleep: Duration,
+}
+
+/// What the daemon has been up to, shared with the status server.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    counters: Mutex<Counters>,
+}
+
+impl Metrics {
+    fn counters(&self) -> std::sync::MutexGuard<'_, Counters> {
+        // Counters stay usable even if a panic left them half updated
+        self.counters.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn record_fetch(&self, source: &'static str, latency: Duration, success: bool) {
+        let mut counters = self.counters();
+        let stats = counters.sources.entry(source).or_default();
+        let seconds = latency.as_secs_f64();
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|&bound| seconds <= bound) {
+            stats.buckets[bucket] += 1;
+        }
+        stats.count += 1;
+        stats.sum += seconds;
+        if success {
+            stats.last_success = Some(Utc::now());
+        } else {
+            stats.failures += 1;
+        }
+    }
+
+    /// Counts a run's deliveries in one go, so readers never see half a run.
+    pub fn record_deliveries(&self, deliveries: &[Delivery]) {
+        let mut counters = self.counters();
+        for delivery in deliveries {
+            counters.rate_limited += u64::from(delivery.retries.count);
+            counters.retry_sleep += delivery.retries.slept;
+            if delivery.result.is_ok() {
+                counters.last_post = Some(Utc::now());
+                *counters.sent.entry(delivery.kind).or_default() += 1;
+            } else {
+                *counters.failed.entry(delivery.kind).or_default() += 1;
+            }
+        }
+    }
+
+    /// Everything in the Prometheus text exposition format.
+    pub fn prometheus(&self) -> String {
+        let counters = self.counters();
+        let mut out = String::new();
+        let _ = writeln!(out, "# HELP splatink_fetch_duration_seconds Time taken to download a splatoon3.ink source.");
+        let _ = writeln!(out, "# TYPE splatink_fetch_duration_seconds histogram");
+        for (source, stats) in &counters.sources {
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(stats.buckets) {
+                cumulative += count;
+                let _ = writeln!(out, "splatink_fetch_duration_seconds_bucket{{source=\"{source}\",le=\"{bound}\"}} {cumulative}");
+            }
+            let _ = writeln!(out, "splatink_fetch_duration_seconds_bucket{{source=\"{source}\",le=\"+Inf\"}} {}", stats.count);
+            let _ = writeln!(out, "splatink_fetch_duration_seconds_sum{{source=\"{source}\"}} {}", stats.sum);
+            let _ = writeln!(out, "splatink_fetch_duration_seconds_count{{source=\"{source}\"}} {}", stats.count);
+        }
+        let _ = writeln!(out, "# HELP splatink_fetch_failures_total Downloads of a splatoon3.ink source that failed.");
+        let _ = writeln!(out, "# TYPE splatink_fetch_failures_total counter");
+        for (source, stats) in &counters.sources {
+            let _ = writeln!(out, "splatink_fetch_failures_total{{source=\"{source}\"}} {}", stats.failures);
+        }
+        let _ = writeln!(out, "# HELP splatink_last_fetch_success_timestamp_seconds When a source was last downloaded successfully.");
+        let _ = writeln!(out, "# TYPE splatink_last_fetch_success_timestamp_seconds gauge");
+        for (source, stats) in &counters.sources {
+            if let Some(last) = stats.last_success {
+                let _ = writeln!(out, "splatink_last_fetch_success_timestamp_seconds{{source=\"{source}\"}} {}", last.timestamp());
+            }
+        }
+        for (name, help, by_kind) in [
+            ("sent", "Notifications delivered to a destination.", &counters.sent),
+            ("failed", "Notifications that could not be delivered to a destination.", &counters.failed),
+        ] {
+            let _ = writeln!(out, "# HELP splatink_notifications_{name}_total {help}");
+            let _ = writeln!(out, "# TYPE splatink_notifications_{name}_total counter");
+            for (kind, count) in by_kind {
+                let _ = writeln!(out, "splatink_notifications_{name}_total{{kind=\"{kind}\"}} {count}");
+            }
+        }
+        let _ = writeln!(out, "# HELP splatink_rate_limited_total Requests answered with 429 Too Many Requests.");
+        let _ = writeln!(out, "# TYPE splatink_rate_limited_total counter");
+        let _ = writeln!(out, "splatink_rate_limited_total {}", counters.rate_limited);
+        let _ = writeln!(out, "# HELP splatink_retry_sleep_seconds_total Time spent waiting out rate limits and retry backoffs.");
+        let _ = writeln!(out, "# TYPE splatink_retry_sleep_seconds_total counter");
+        let _ = writeln!(out, "splatink_retry_sleep_seconds_total {}", counters.retry_sleep.as_secs_f64());
+        if let Some(last) = counters.last_post {
+            let _ = writeln!(out, "# HELP splatink_last_post_success_timestamp_seconds When a notification was last delivered.");
+            let _ = writeln!(out, "# TYPE splatink_last_post_success_timestamp_seconds gauge");
+            let _ = writeln!(out, "splatink_last_post_success_timestamp_seconds {}", last.timestamp());
+        }
+        out
+    }
+
+    /// Healthy while every source has been fetched within the last few polls.
+    fn health(&self, poll_interval: TimeDelta, sources: &[&'static str]) -> (bool, serde_json::Value) {
+        let counters = self.counters();
+        let cutoff = Utc::now() - poll_interval * MISSED_POLLS;
+        let last_success = |source: &str| counters.sources.get(source).and_then(|stats| stats.last_success);
+        let healthy = sources.iter().all(|source| last_success(source).is_some_and(|last| last > cutoff));
+        let body = json!({
+            "status": if healthy { "ok" } else { "unhealthy" },
+            "sources": sources.iter().map(|&source| (source.to_owned(), json!({ "last_success": last_success(source) }))).collect::<serde_json::Map<_, _>>(),
+            "last_post": counters.last_post,
+        });
+        (healthy, body)
+    }
+}
+
+/// A [`Fetcher`] that reports each download's latency and outcome to [`Metrics`].
+pub struct Timed<'a, F> {
+    pub fetcher: &'a F,
+    pub metrics: &'a Metrics,
+    pub source: &'static str,
+}
+
+impl<F: Fetcher> Fetcher for Timed<'_, F> {
+    async fn fetch(&self, url: &str) -> Result<String> {
+        let started = Instant::now();
+        let result = self.fetcher.fetch(url).await;
+        self.metrics.record_fetch(self.source, started.elapsed(), result.is_ok());
+        result
+    }
+
+    async fn fetch_if_modified(&self, url: &str, validators: &Validators) -> Result<Conditional> {
+        let started = Instant::now();
+        let result = self.fetcher.fetch_if_modified(url, validators).await;
+        self.metrics.record_fetch(self.source, started.elapsed(), result.is_ok());
+        result
+    }
+}
+
+#[derive(Clone)]
+struct AppState<St> {
+    metrics: Arc<Metrics>,
+    poll_interval: TimeDelta,
+    sources: &'static [&'static str],
+    store: St,
+}
+
+/// Serves the status endpoints on `listen` until `shutdown` turns true, `/state` being read from the bot's `store`.
+pub async fn serve<St>(listen: SocketAddr, metrics: Arc<Metrics>, poll_interval: TimeDelta, sources: &'static [&'static str], store: St, mut shutdown: watch::Receiver<bool>) -> Result<()>
+where
+    St: StateStore + Clone + Send + Sync + 'static,
+{
+    let app = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/metrics", get(metrics_text))
+        .route("/state", get(state))
+        .with_state(AppState { metrics, poll_interval, sources, store })
+    ;
+    let listener = TcpListener::bind(listen).await?;
+    println!("Status server listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            let _ = shutdown.wait_for(|stop| *stop).await;
+        })
+        .await?
+    ;
+    Ok(())
+}
+
+async fn healthz<St>(State(app): State<AppState<St>>) -> Response {
+    let (healthy, body) = app.metrics.health(app.poll_interval, app.sources);
+    let status = if healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(body)).into_response()
+}
+
+async fn metrics_text<St>(State(app): State<AppState<St>>) -> Response {
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], app.metrics.prometheus()).into_response()
+}
+
+/// The schedule cache as last committed by a run.
+async fn state<St: StateStore>(State(app): State<AppState<St>>) -> Response {
+    match app.store.cached(SCHEDULES_CACHE) {
+        Ok(Some(json)) => ([(header::CONTENT_TYPE, "application/json")], json).into_response(),
+        Ok(None) => (StatusCode::SERVICE_UNAVAILABLE, "no cached schedule yet").into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, format!("reading the cached schedule failed: {err}")).into_response(),
+    }
+}
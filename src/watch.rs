@@ -0,0 +1,113 @@
+use serde::Deserialize;
+
+use crate::{diff::{diff, Change}, rotation::Rotation, schedule_data::{coopStage, salmonRunRotation, weapon}, Notification};
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchlistConfig {
+    /// Regular Salmon Run rotations worth announcing, nothing is announced when empty.
+    pub entries: Vec<WatchEntry>,
+}
+
+/// Matches rotations by weapon and stage. Left out fields match anything.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchEntry {
+    /// Weapon name like `Hydra Splatling` or its splatoon3.ink ID.
+    pub weapon: Option<String>,
+    /// Stage name like `Sockeye Station` or its `coopStage` ID.
+    pub stage: Option<String>,
+    /// Role ID pinged instead of the `watched` kind's role.
+    pub role: Option<String>,
+}
+
+impl WatchEntry {
+    /// What matched, or `None` when the rotation isn't watched by this entry.
+    fn matches(&self, weapons: &[weapon; 4], stage: &coopStage) -> Option<Vec<String>> {
+        let mut watched = vec![];
+        if let Some(wanted) = &self.weapon {
+            let weapon = weapons.iter().find(|weapon| wanted.eq_ignore_ascii_case(&weapon.name) || wanted == &weapon.__splatoon3ink_id)?;
+            watched.push(weapon.name.clone());
+        }
+        if let Some(wanted) = &self.stage {
+            if !wanted.eq_ignore_ascii_case(&stage.name) && wanted != &stage.id {
+                return None;
+            }
+            watched.push(stage.name.clone());
+        }
+        Some(watched)
+    }
+}
+
+/// Announcements for regular rotations that newly match the watchlist and aren't announced otherwise,
+/// either because they were just scheduled or because splatoon3.ink revised them into a watched one.
+pub fn rotation_notifications(config: &WatchlistConfig, file_nodes: &[salmonRunRotation], internet_nodes: &[salmonRunRotation]) -> Vec<Notification> {
+    if config.entries.is_empty() {
+        return vec![];
+    }
+    diff(file_nodes, internet_nodes).into_iter()
+        .filter_map(|change| match change {
+            Change::Added(rotation) => watched(config, &rotation),
+            // Already announced if the old copy matched, whatever matches now
+            Change::Changed{old, new} => watched(config, &old).is_none().then(|| watched(config, &new)).flatten(),
+            Change::Removed(_) => None,
+        })
+        .collect()
+}
+
+fn watched(config: &WatchlistConfig, rotation: &salmonRunRotation) -> Option<Notification> {
+    if rotation.notification().is_some() {
+        return None;
+    }
+    let setting = &rotation.setting;
+    let matches: Vec<_> = config.entries.iter()
+        .filter_map(|entry| entry.matches(&setting.weapons, &setting.coopStage).map(|matched| (entry, matched)))
+        .collect()
+    ;
+    if matches.is_empty() {
+        return None;
+    }
+    let mut watched: Vec<String> = vec![];
+    for name in matches.iter().flat_map(|(_, matched)| matched) {
+        if !watched.contains(name) {
+            watched.push(name.clone());
+        }
+    }
+    Some(Notification::WatchedRotation {
+        start: rotation.startTime.to_utc(),
+        end: rotation.endTime.to_utc(),
+        weapons: setting.weapons.iter().map(|weapon| weapon.name.clone()).collect(),
+        king: rotation.__splatoon3ink_king_salmonid_guess.clone(),
+        stage: (setting.coopStage.name.clone(), setting.coopStage.image.url.clone()),
+        watched,
+        roles: matches.iter().filter_map(|(entry, _)| entry.role.clone()).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule_data::RotationData;
+
+    fn entry(weapon: Option<&str>, stage: Option<&str>) -> WatchEntry {
+        WatchEntry { weapon: weapon.map(String::from), stage: stage.map(String::from), role: None }
+    }
+
+    #[test]
+    fn revised_rotations_are_announced_once_they_match() {
+        let data: RotationData = serde_json::from_str(include_str!("../tests/fixtures/schedules_salmon_variants.json")).unwrap();
+        let old = &data.data.coopGroupingSchedule.regularSchedules.nodes[..1];
+        let mut new = old.to_vec();
+        new[0].setting.coopStage.name = "Salmonid Smokeyard".to_string();
+
+        let config = WatchlistConfig { entries: vec![entry(None, Some("salmonid smokeyard"))] };
+        assert!(matches!(
+            rotation_notifications(&config, old, &new).as_slice(),
+            [Notification::WatchedRotation { watched, .. }] if watched == &["Salmonid Smokeyard"],
+        ));
+
+        // Matched before the revision too, so it was announced already
+        let config = WatchlistConfig { entries: vec![entry(Some("Hydra Splatling"), None)] };
+        assert!(rotation_notifications(&config, old, &new).is_empty());
+    }
+}
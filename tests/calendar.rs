@@ -0,0 +1,60 @@
+//! Builds the iCalendar feed from the splatoon3.ink stand-in.
+
+mod common;
+
+use std::fs;
+
+use common::{run_with, splatoon3ink, workdir};
+
+const CONFIG: &str = "webhook_url = \"http://localhost/unused\"";
+
+#[tokio::test]
+async fn calendar_is_written_without_touching_the_caches() {
+    let splatoon3ink = splatoon3ink().await;
+    let dir = workdir(&splatoon3ink, CONFIG);
+    let cache = fs::read_to_string(dir.path().join("Schedules Json.json")).unwrap();
+
+    let output = run_with(dir.path(), &["calendar", "--output", "events.ics"]).await;
+    assert!(output.status.success());
+    let ics = fs::read_to_string(dir.path().join("events.ics")).unwrap();
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert!(ics.contains("SUMMARY:Big Run on "));
+    assert!(ics.contains("SUMMARY:Tricolor Battles: Splatfest: "));
+    assert!(ics.contains("DESCRIPTION:King Salmonid: "));
+    assert!(ics.contains("TRIGGER:-PT30M"));
+    // The events keep their UIDs from one export to the next
+    let uids = |ics: &str| ics.lines().filter(|line| line.starts_with("UID:")).map(str::to_owned).collect::<Vec<_>>();
+    run_with(dir.path(), &["calendar", "--output", "again.ics"]).await;
+    assert_eq!(uids(&ics), uids(&fs::read_to_string(dir.path().join("again.ics")).unwrap()));
+    assert_eq!(fs::read_to_string(dir.path().join("Schedules Json.json")).unwrap(), cache);
+}
+
+#[tokio::test]
+async fn calendar_is_served_over_http() {
+    let splatoon3ink = splatoon3ink().await;
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let dir = workdir(&splatoon3ink, &format!("{CONFIG}\n[calendar]\nlisten = \"127.0.0.1:{port}\""));
+    let _server = tokio::process::Command::new(env!("CARGO_BIN_EXE_splatink_discord_webhook"))
+        .args(["calendar", "--serve"])
+        .current_dir(dir.path())
+        .env_clear()
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap()
+    ;
+
+    let url = format!("http://127.0.0.1:{port}/calendar.ics");
+    let mut response = None;
+    for _ in 0..50 {
+        match reqwest::get(&url).await {
+            Ok(ok) => {
+                response = Some(ok);
+                break;
+            },
+            Err(_) => tokio::time::sleep(std::time::Duration::from_millis(100)).await,
+        }
+    }
+    let response = response.expect("calendar server did not come up");
+    assert_eq!(response.headers()["content-type"], "text/calendar; charset=utf-8");
+    assert!(response.text().await.unwrap().contains("SUMMARY:Big Run on "));
+}
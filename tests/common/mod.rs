@@ -0,0 +1,139 @@
+//! Stand-ins shared by the integration tests.
+
+// Each test binary only uses some of these
+#![allow(dead_code)]
+
+use std::{fs, path::Path, process::Output};
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use tempfile::TempDir;
+use tokio::process::{Child, Command};
+use wiremock::{matchers::{method, path}, Mock, MockServer, Request, ResponseTemplate};
+
+const SCHEDULES_OLD: &str = include_str!("../fixtures/schedules_old.json");
+pub const SCHEDULES_NEW: &str = include_str!("../fixtures/schedules_new.json");
+const FESTIVALS_OLD: &str = include_str!("../fixtures/festivals_old.json");
+pub const FESTIVALS_NEW: &str = include_str!("../fixtures/festivals_new.json");
+
+/// Random rotation, Big Run, Eggstra Work and Splatfest between the old and new fixtures.
+pub const EXPECTED_POSTS: usize = 4;
+
+/// `Last-Modified` of every document [`splatoon3ink`] serves, the `ETag` being `"<file name>"`.
+pub const LAST_MODIFIED: &str = "Mon, 01 Jan 2024 00:00:00 GMT";
+
+/// splatoon3.ink serving the new fixtures.
+pub async fn splatoon3ink() -> MockServer {
+    splatoon3ink_serving(SCHEDULES_NEW, FESTIVALS_NEW).await
+}
+
+/// splatoon3.ink serving `schedules` and `festivals`.
+pub async fn splatoon3ink_serving(schedules: &str, festivals: &str) -> MockServer {
+    let server = MockServer::start().await;
+    serve_documents(&server, schedules, festivals).await;
+    server
+}
+
+/// Has the splatoon3.ink stand-in `server` serve `schedules` and `festivals` from now on.
+pub async fn serve_documents(server: &MockServer, schedules: &str, festivals: &str) {
+    server.reset().await;
+    for (name, body) in [("schedules.json", schedules), ("festivals.json", festivals)] {
+        Mock::given(method("GET")).and(path(format!("/data/{name}")))
+            .respond_with(ResponseTemplate::new(200)
+                .set_body_raw(body, "application/json")
+                .insert_header("etag", format!("\"{name}\"").as_str())
+                .insert_header("last-modified", LAST_MODIFIED)
+            )
+            .mount(server)
+            .await
+        ;
+    }
+}
+
+/// The new schedules with the Big Run moved to have started an hour ago, the fixtures' other events having long ended.
+pub fn schedules_with_ongoing_big_run() -> String {
+    let now = chrono::Utc::now();
+    schedules_with_big_run(now - chrono::TimeDelta::hours(1), now + chrono::TimeDelta::hours(47)).to_string()
+}
+
+/// The new schedules with the Big Run moved to `start`..`end`.
+pub fn schedules_with_big_run(start: DateTime<Utc>, end: DateTime<Utc>) -> Value {
+    let mut schedules: Value = serde_json::from_str(SCHEDULES_NEW).unwrap();
+    let big_run = big_run(&mut schedules);
+    big_run["startTime"] = start.to_rfc3339().into();
+    big_run["endTime"] = end.to_rfc3339().into();
+    schedules
+}
+
+/// The first Big Run of a schedules document.
+pub fn big_run(schedules: &mut Value) -> &mut Value {
+    &mut schedules["data"]["coopGroupingSchedule"]["bigRunSchedules"]["nodes"][0]
+}
+
+/// A working directory whose caches hold the old fixtures, configured with `config` and the splatoon3.ink stand-in.
+pub fn workdir(splatoon3ink: &MockServer, config: &str) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("Schedules Json.json"), SCHEDULES_OLD).unwrap();
+    fs::write(dir.path().join("Splatfest Json.json"), FESTIVALS_OLD).unwrap();
+    fs::write(dir.path().join("config.toml"), format!(
+        "{config}\n\
+        [sources]\n\
+        schedules_url = \"{splatoon3ink}/data/schedules.json\"\n\
+        festivals_url = \"{splatoon3ink}/data/festivals.json\"\n",
+        splatoon3ink = splatoon3ink.uri(),
+    )).unwrap();
+    dir
+}
+
+pub async fn run(dir: &Path) -> Output {
+    run_with(dir, &[]).await
+}
+
+pub async fn run_with(dir: &Path, args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_splatink_discord_webhook"))
+        .args(args)
+        .current_dir(dir)
+        .env_clear()
+        .output()
+        .await
+        .unwrap()
+    ;
+    println!("{}", String::from_utf8_lossy(&output.stdout));
+    eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+    output
+}
+
+/// Starts the binary in the background, it is killed once the returned handle is dropped.
+pub fn spawn(dir: &Path, args: &[&str]) -> Child {
+    Command::new(env!("CARGO_BIN_EXE_splatink_discord_webhook"))
+        .args(args)
+        .current_dir(dir)
+        .env_clear()
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap()
+}
+
+/// A port nothing is listening on right now.
+pub fn free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+/// `GET`s `url`, retrying while a server started with [`spawn`] is still coming up.
+pub async fn get_when_up(url: &str) -> reqwest::Response {
+    for _ in 0..50 {
+        if let Ok(response) = reqwest::get(url).await {
+            return response;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("nothing came up at {url}");
+}
+
+/// Bodies of the requests made with `method`.
+pub fn bodies(requests: &[Request], method: &str) -> Vec<Value> {
+    requests.iter()
+        .filter(|request| request.method.as_str() == method)
+        .map(|request| serde_json::from_slice(&request.body).unwrap())
+        .collect()
+}
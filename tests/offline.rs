@@ -0,0 +1,488 @@
+//! Runs the binary against a local stand-in for splatoon3.ink and a fake Discord webhook.
+
+mod common;
+
+use std::fs;
+
+use serde_json::Value;
+use tempfile::TempDir;
+use wiremock::{matchers::{header, method, path, query_param}, Mock, MockServer, Request, ResponseTemplate};
+
+use common::{run, splatoon3ink, EXPECTED_POSTS};
+
+const WEBHOOK_PATH: &str = "/api/webhooks/1/token";
+
+fn workdir(splatoon3ink: &MockServer, discord: &MockServer) -> TempDir {
+    common::workdir(splatoon3ink, &format!("webhook_url = \"{}{WEBHOOK_PATH}\"", discord.uri()))
+}
+
+fn posts(requests: &[Request]) -> Vec<Value> {
+    common::bodies(requests, "POST")
+}
+
+fn titles(posts: &[Value]) -> Vec<&str> {
+    let mut titles: Vec<_> = posts.iter().filter_map(|post| post["embeds"][0]["title"].as_str()).collect();
+    titles.sort();
+    titles
+}
+
+fn sent_message() -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(serde_json::json!({ "id": "1000" }))
+}
+
+#[tokio::test]
+async fn new_events_are_posted_once() {
+    let splatoon3ink = common::splatoon3ink_serving(&common::schedules_with_ongoing_big_run(), common::FESTIVALS_NEW).await;
+    let discord = MockServer::start().await;
+    Mock::given(method("POST")).and(path(WEBHOOK_PATH)).and(query_param("wait", "true"))
+        .respond_with(sent_message())
+        .mount(&discord)
+        .await
+    ;
+    let dir = workdir(&splatoon3ink, &discord);
+
+    let output = run(dir.path()).await;
+    assert!(output.status.success());
+    let sent = posts(&discord.received_requests().await.unwrap());
+    assert_eq!(titles(&sent), [
+        "A Big Run alert has been broadcasted!",
+        "A Random Rotation has been added to the schedule!",
+        "A Splatfest has been announced!",
+        "Eggstra Workers are needed at Grizzco!",
+    ]);
+    // Only the Big Run is still on, the message IDs of the ended events are dropped
+    let state = fs::read_to_string(dir.path().join("State Json.json")).unwrap();
+    assert_eq!(state.matches("\"1000\"").count(), 1, "{state}");
+    assert!(state.contains("\"messages\":{\"big_run:"), "{state}");
+
+    // The caches now match splatoon3.ink, so nothing is new the second time
+    discord.reset().await;
+    Mock::given(method("POST")).respond_with(sent_message()).mount(&discord).await;
+    let output = run(dir.path()).await;
+    assert!(output.status.success());
+    assert!(posts(&discord.received_requests().await.unwrap()).is_empty());
+}
+
+#[tokio::test]
+async fn rate_limited_posts_are_retried() {
+    let splatoon3ink = splatoon3ink().await;
+    let discord = MockServer::start().await;
+    Mock::given(method("POST")).and(path(WEBHOOK_PATH))
+        .respond_with(ResponseTemplate::new(429).set_body_json(serde_json::json!({
+            "message": "You are being rate limited.",
+            "retry_after": 0.05,
+            "global": false,
+        })))
+        .up_to_n_times(2)
+        .with_priority(1)
+        .mount(&discord)
+        .await
+    ;
+    Mock::given(method("POST")).and(path(WEBHOOK_PATH))
+        .respond_with(sent_message())
+        .mount(&discord)
+        .await
+    ;
+    let dir = workdir(&splatoon3ink, &discord);
+
+    let output = run(dir.path()).await;
+    assert!(output.status.success());
+    assert_eq!(posts(&discord.received_requests().await.unwrap()).len(), EXPECTED_POSTS + 2);
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&format!("Notifs sent: {EXPECTED_POSTS} | Notifs failed: 0")));
+}
+
+#[tokio::test]
+async fn server_errors_count_as_failed() {
+    let splatoon3ink = splatoon3ink().await;
+    let discord = MockServer::start().await;
+    Mock::given(method("POST")).and(path(WEBHOOK_PATH))
+        .respond_with(ResponseTemplate::new(502).set_body_string("Bad Gateway"))
+        .mount(&discord)
+        .await
+    ;
+    let dir = common::workdir(&splatoon3ink, &format!("webhook_url = \"{}{WEBHOOK_PATH}\"\n[sending]\nmax_retries = 1\nbackoff_ms = 1", discord.uri()));
+
+    let output = run(dir.path()).await;
+    assert!(output.status.success());
+    // Server errors are retried, unlike a deleted webhook
+    assert_eq!(posts(&discord.received_requests().await.unwrap()).len(), EXPECTED_POSTS * 2);
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&format!("Notifs sent: 0 | Notifs failed: {EXPECTED_POSTS}")));
+    let state = fs::read_to_string(dir.path().join("State Json.json")).unwrap();
+    assert!(!state.contains("\"1000\""));
+}
+
+#[tokio::test]
+async fn deleted_webhooks_disable_their_destination() {
+    let splatoon3ink = splatoon3ink().await;
+    let discord = MockServer::start().await;
+    Mock::given(method("POST")).and(path(WEBHOOK_PATH))
+        .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({ "message": "Unknown Webhook", "code": 10015 })))
+        .mount(&discord)
+        .await
+    ;
+    let dir = workdir(&splatoon3ink, &discord);
+
+    let output = run(dir.path()).await;
+    assert!(output.status.success());
+    // The first failure stops the rest of the queue
+    assert_eq!(posts(&discord.received_requests().await.unwrap()).len(), 1);
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&format!("Notifs sent: 0 | Notifs failed: {EXPECTED_POSTS}")));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("disabled, its webhook is gone or unauthorized: discord error: 404 Not Found (code 10015): Unknown Webhook"));
+
+    // Nothing goes out again, even with the same events new once more
+    fs::write(dir.path().join("Schedules Json.json"), include_str!("fixtures/schedules_old.json")).unwrap();
+    let output = run(dir.path()).await;
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Skipping disabled destination"));
+    assert_eq!(posts(&discord.received_requests().await.unwrap()).len(), 1);
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_retried_without_repeating_the_others() {
+    let splatoon3ink = splatoon3ink().await;
+    let discord = MockServer::start().await;
+    Mock::given(method("POST")).and(path("/flaky"))
+        .respond_with(ResponseTemplate::new(502).set_body_string("Bad Gateway"))
+        .up_to_n_times(EXPECTED_POSTS as u64)
+        .mount(&discord)
+        .await
+    ;
+    Mock::given(method("POST")).respond_with(sent_message()).mount(&discord).await;
+    let uri = discord.uri();
+    let dir = common::workdir(&splatoon3ink, &format!(
+        "[[destinations]]\nname = \"steady\"\nwebhook_url = \"{uri}/steady\"\n\
+        [[destinations]]\nname = \"flaky\"\nwebhook_url = \"{uri}/flaky\"\n\
+        [sending]\nmax_retries = 0\n\
+        [state]\ndir = \"state\"\n",
+    ));
+    fs::create_dir(dir.path().join("state")).unwrap();
+    for cache in ["Schedules Json.json", "Splatfest Json.json"] {
+        fs::rename(dir.path().join(cache), dir.path().join("state").join(cache)).unwrap();
+    }
+    let old_cache = fs::read_to_string(dir.path().join("state/Schedules Json.json")).unwrap();
+
+    let output = run(dir.path()).await;
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("flaky: sent 0 | failed 4"));
+    // Nothing is committed while a delivery can still go through
+    assert_eq!(fs::read_to_string(dir.path().join("state/Schedules Json.json")).unwrap(), old_cache);
+
+    let output = run(dir.path()).await;
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("flaky: sent 4 | failed 0"));
+    let requests = discord.received_requests().await.unwrap();
+    let to = |path: &str| requests.iter().filter(|request| request.url.path() == path).count();
+    assert_eq!((to("/steady"), to("/flaky")), (EXPECTED_POSTS, EXPECTED_POSTS * 2));
+    assert_ne!(fs::read_to_string(dir.path().join("state/Schedules Json.json")).unwrap(), old_cache);
+    let state: Value = serde_json::from_str(&fs::read_to_string(dir.path().join("state/State Json.json")).unwrap()).unwrap();
+    assert_eq!(state["version"], 3);
+    assert!(state["destinations"].as_object().unwrap().values().all(|destination| destination["delivered"] == serde_json::json!({})));
+}
+
+#[tokio::test]
+async fn unchanged_sources_are_not_downloaded_or_diffed() {
+    let splatoon3ink = splatoon3ink().await;
+    let discord = MockServer::start().await;
+    Mock::given(method("POST")).respond_with(sent_message()).mount(&discord).await;
+    let dir = workdir(&splatoon3ink, &discord);
+    assert!(run(dir.path()).await.status.success());
+
+    for name in ["schedules.json", "festivals.json"] {
+        Mock::given(method("GET")).and(path(format!("/data/{name}"))).and(header("if-none-match", format!("\"{name}\"").as_str()))
+            .respond_with(ResponseTemplate::new(304))
+            .with_priority(1)
+            .mount(&splatoon3ink)
+            .await
+        ;
+    }
+    // Neither copy is read when splatoon3.ink has nothing new
+    fs::write(dir.path().join("Schedules Json.json"), "not json").unwrap();
+    fs::write(dir.path().join("Splatfest Json.json"), "not json").unwrap();
+    let output = run(dir.path()).await;
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Notifs sent: 0 | Notifs failed: 0"));
+    let conditional: Vec<_> = splatoon3ink.received_requests().await.unwrap().into_iter()
+        .filter(|request| request.headers.contains_key("if-none-match"))
+        .collect()
+    ;
+    assert_eq!(conditional.len(), 2);
+    assert!(conditional.iter().all(|request| request.headers.get("if-modified-since").is_some_and(|value| value == common::LAST_MODIFIED)));
+    assert_eq!(fs::read_to_string(dir.path().join("Schedules Json.json")).unwrap(), "not json");
+}
+
+/// The `<t:...:f>` start time shown in a post's first field.
+fn start_time(post: &Value) -> i64 {
+    let value = post["embeds"][0]["fields"][0]["value"].as_str().unwrap();
+    value.trim_start_matches("<t:").trim_end_matches(":f>").parse().unwrap()
+}
+
+#[tokio::test]
+async fn exhausted_buckets_are_waited_out_in_start_order() {
+    let splatoon3ink = splatoon3ink().await;
+    let discord = MockServer::start().await;
+    Mock::given(method("POST")).and(path(WEBHOOK_PATH))
+        .respond_with(sent_message()
+            .insert_header("x-ratelimit-bucket", "webhook")
+            .insert_header("x-ratelimit-remaining", "0")
+            .insert_header("x-ratelimit-reset-after", "0.2")
+        )
+        .mount(&discord)
+        .await
+    ;
+    let dir = workdir(&splatoon3ink, &discord);
+
+    let started = std::time::Instant::now();
+    let output = run(dir.path()).await;
+    assert!(output.status.success());
+    assert!(started.elapsed() >= std::time::Duration::from_millis(200) * (EXPECTED_POSTS as u32 - 1));
+    let starts: Vec<_> = posts(&discord.received_requests().await.unwrap()).iter().map(start_time).collect();
+    assert_eq!(starts.len(), EXPECTED_POSTS);
+    assert!(starts.is_sorted(), "posted out of order: {starts:?}");
+}
+
+#[tokio::test]
+async fn rate_limit_retries_are_capped() {
+    let splatoon3ink = splatoon3ink().await;
+    let discord = MockServer::start().await;
+    Mock::given(method("POST")).and(path(WEBHOOK_PATH))
+        .respond_with(ResponseTemplate::new(429).set_body_json(serde_json::json!({
+            "message": "You are being rate limited.",
+            "retry_after": 0.01,
+            "global": false,
+        })))
+        .mount(&discord)
+        .await
+    ;
+    let dir = common::workdir(&splatoon3ink, &format!("webhook_url = \"{}{WEBHOOK_PATH}\"\n[sending]\nmax_retries = 2", discord.uri()));
+
+    let output = run(dir.path()).await;
+    assert!(output.status.success());
+    assert_eq!(posts(&discord.received_requests().await.unwrap()).len(), EXPECTED_POSTS * 3);
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&format!("Notifs sent: 0 | Notifs failed: {EXPECTED_POSTS}")));
+}
+
+#[tokio::test]
+async fn failing_sources_fall_back_to_their_copy_and_alert_once() {
+    let splatoon3ink = splatoon3ink().await;
+    Mock::given(method("GET")).and(path("/data/schedules.json"))
+        .respond_with(ResponseTemplate::new(502).set_body_string("Bad Gateway"))
+        .with_priority(1)
+        .mount(&splatoon3ink)
+        .await
+    ;
+    let discord = MockServer::start().await;
+    Mock::given(method("POST")).respond_with(sent_message()).mount(&discord).await;
+    let dir = common::workdir(&splatoon3ink, &format!(
+        "webhook_url = \"{discord}{WEBHOOK_PATH}\"\n[alerts]\nwebhook_url = \"{discord}/alerts\"\nstale_after_minutes = 0",
+        discord = discord.uri(),
+    ));
+    // Appended to the `[sources]` table `workdir` ends with
+    let config = dir.path().join("config.toml");
+    fs::write(&config, fs::read_to_string(&config).unwrap() + "retries = 1\nbackoff_ms = 1\n").unwrap();
+
+    for _ in 0..2 {
+        let output = run(dir.path()).await;
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).contains("Stale sources: schedules"));
+    }
+    let requests = splatoon3ink.received_requests().await.unwrap();
+    assert_eq!(requests.iter().filter(|request| request.url.path() == "/data/schedules.json").count(), 4);
+    let requests = discord.received_requests().await.unwrap();
+    let (alerts, notifications): (Vec<_>, Vec<_>) = requests.into_iter().partition(|request| request.url.path() == "/alerts");
+    // Only the Splatfest was new, the schedules copy is kept for next time
+    assert_eq!(titles(&posts(&notifications)).len(), 1);
+    let alerts = posts(&alerts);
+    assert_eq!(alerts.len(), 1);
+    assert!(alerts[0]["content"].as_str().unwrap().contains("No live schedules data"));
+    assert_eq!(fs::read_to_string(dir.path().join("Schedules Json.json")).unwrap(), include_str!("fixtures/schedules_old.json"));
+}
+
+#[tokio::test]
+async fn reminders_without_message_ids_are_sent_once() {
+    // The Big Run started an hour ago, so its "starting now" reminder is due until it ends
+    let splatoon3ink = common::splatoon3ink_serving(&common::schedules_with_ongoing_big_run(), common::FESTIVALS_NEW).await;
+    let slack = MockServer::start().await;
+    Mock::given(method("POST")).respond_with(ResponseTemplate::new(200).set_body_string("ok")).mount(&slack).await;
+    let dir = common::workdir(&splatoon3ink, &format!(
+        "[[destinations]]\nname = \"slack\"\nbackend = \"slack\"\nwebhook_url = \"{}/slack\"\n\
+        [reminders]\noffsets_minutes = [0]\nkinds = [\"big_run\"]",
+        slack.uri(),
+    ));
+
+    for _ in 0..3 {
+        assert!(run(dir.path()).await.status.success());
+    }
+    let headers: Vec<_> = posts(&slack.received_requests().await.unwrap()).into_iter()
+        .filter_map(|post| post["blocks"][0]["text"]["text"].as_str().map(str::to_owned))
+        .collect()
+    ;
+    assert_eq!(headers.iter().filter(|header| *header == splatink_discord_webhook::Notification::TITLE_STARTING_NOW).count(), 1, "{headers:?}");
+    let state = fs::read_to_string(dir.path().join("State Json.json")).unwrap();
+    assert!(state.contains("\"reminded\":{\"reminder:big_run:"), "{state}");
+}
+
+#[tokio::test]
+async fn a_failing_destination_holds_back_only_its_source_and_only_for_a_while() {
+    let splatoon3ink = splatoon3ink().await;
+    let discord = MockServer::start().await;
+    Mock::given(method("POST")).and(path("/down"))
+        .respond_with(ResponseTemplate::new(502).set_body_string("Bad Gateway"))
+        .mount(&discord)
+        .await
+    ;
+    Mock::given(method("POST")).respond_with(sent_message()).mount(&discord).await;
+    let uri = discord.uri();
+    let dir = common::workdir(&splatoon3ink, &format!(
+        "[[destinations]]\nname = \"steady\"\nwebhook_url = \"{uri}/steady\"\n\
+        [[destinations]]\nname = \"down\"\nwebhook_url = \"{uri}/down\"\nkinds = [\"splatfest\"]\n\
+        [sending]\nmax_retries = 0\nmax_held_runs = 1\n",
+    ));
+    let read = |name: &str| fs::read_to_string(dir.path().join(name)).unwrap();
+    let (old_schedules, old_festivals) = (read("Schedules Json.json"), read("Splatfest Json.json"));
+
+    assert!(run(dir.path()).await.status.success());
+    // Only the festivals failed, the schedules move on
+    assert_ne!(read("Schedules Json.json"), old_schedules);
+    assert_eq!(read("Splatfest Json.json"), old_festivals);
+
+    let output = run(dir.path()).await;
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("down: sent 0 | failed 1"));
+    assert_ne!(read("Splatfest Json.json"), old_festivals);
+
+    assert!(run(dir.path()).await.status.success());
+    let requests = discord.received_requests().await.unwrap();
+    let to = |path: &str| requests.iter().filter(|request| request.url.path() == path).count();
+    assert_eq!((to("/steady"), to("/down")), (EXPECTED_POSTS, 2));
+    let state: Value = serde_json::from_str(&read("State Json.json")).unwrap();
+    assert!(state["destinations"].as_object().unwrap().values().all(|destination| destination["delivered"] == serde_json::json!({})));
+    // Nothing was left to fail in the last run
+    assert_eq!(state["destinations"]["down"]["held_runs"], 0);
+}
+
+#[tokio::test]
+async fn a_source_that_cannot_be_fetched_keeps_the_rest_of_the_run() {
+    let splatoon3ink = splatoon3ink().await;
+    Mock::given(method("GET")).and(path("/data/festivals.json"))
+        .respond_with(ResponseTemplate::new(404))
+        .with_priority(1)
+        .mount(&splatoon3ink)
+        .await
+    ;
+    let discord = MockServer::start().await;
+    Mock::given(method("POST")).respond_with(sent_message()).mount(&discord).await;
+    let dir = workdir(&splatoon3ink, &discord);
+    // Without a committed copy there is nothing to fall back to
+    fs::remove_file(dir.path().join("Splatfest Json.json")).unwrap();
+
+    let output = run(dir.path()).await;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&format!("Notifs sent: {} | Notifs failed: 0", EXPECTED_POSTS - 1)));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Fetching festivals failed: http error 404 Not Found"));
+}
+
+#[tokio::test]
+async fn a_revised_stage_is_corrected_not_cancelled() {
+    let now = chrono::Utc::now();
+    let mut schedules = common::schedules_with_big_run(now + chrono::TimeDelta::days(1), now + chrono::TimeDelta::days(3));
+    let splatoon3ink = common::splatoon3ink_serving(&schedules.to_string(), common::FESTIVALS_NEW).await;
+    let discord = MockServer::start().await;
+    Mock::given(method("POST")).respond_with(sent_message()).mount(&discord).await;
+    let dir = workdir(&splatoon3ink, &discord);
+    assert!(run(dir.path()).await.status.success());
+
+    // splatoon3.ink moves the announced Big Run to another stage
+    let stage = &mut common::big_run(&mut schedules)["setting"]["coopStage"];
+    stage["id"] = "Q29vcFN0YWdlLTk=".into();
+    stage["name"] = "Jammin' Salmon Junction".into();
+    common::serve_documents(&splatoon3ink, &schedules.to_string(), common::FESTIVALS_NEW).await;
+    discord.reset().await;
+    Mock::given(method("POST")).respond_with(sent_message()).mount(&discord).await;
+    Mock::given(method("DELETE")).respond_with(ResponseTemplate::new(204)).mount(&discord).await;
+    assert!(run(dir.path()).await.status.success());
+
+    let requests = discord.received_requests().await.unwrap();
+    let methods: Vec<_> = requests.iter().map(|request| request.method.as_str()).collect();
+    assert_eq!(methods, ["POST"]);
+    let sent = posts(&requests);
+    assert_eq!(titles(&sent), [splatink_discord_webhook::Notification::TITLE_CORRECTION]);
+    let fields = sent[0]["embeds"][0]["fields"].to_string();
+    assert!(fields.contains("Jammin' Salmon Junction"), "{fields}");
+}
+
+#[tokio::test]
+async fn a_held_cancellation_is_retried_after_its_slots_new_announcement_went_out() {
+    let now = chrono::Utc::now();
+    let start = now + chrono::TimeDelta::days(1);
+    let mut schedules = common::schedules_with_big_run(start, now + chrono::TimeDelta::days(3));
+    let splatoon3ink = common::splatoon3ink_serving(&schedules.to_string(), common::FESTIVALS_NEW).await;
+    let discord = MockServer::start().await;
+    Mock::given(method("POST")).respond_with(sent_message()).mount(&discord).await;
+    let dir = common::workdir(&splatoon3ink, &format!("webhook_url = \"{}{WEBHOOK_PATH}\"\n[sending]\nmax_retries = 0", discord.uri()));
+    assert!(run(dir.path()).await.status.success());
+
+    // The Big Run now ends a day later, a new slot starting at the same time: the old post is deleted and the new one announced
+    common::big_run(&mut schedules)["endTime"] = (now + chrono::TimeDelta::days(4)).to_rfc3339().into();
+    common::serve_documents(&splatoon3ink, &schedules.to_string(), common::FESTIVALS_NEW).await;
+    discord.reset().await;
+    Mock::given(method("POST")).respond_with(sent_message()).mount(&discord).await;
+    Mock::given(method("DELETE")).respond_with(ResponseTemplate::new(502).set_body_string("Bad Gateway")).mount(&discord).await;
+    assert!(run(dir.path()).await.status.success());
+    let methods = |requests: Vec<Request>| requests.iter().map(|request| request.method.to_string()).collect::<Vec<_>>();
+    assert_eq!(methods(discord.received_requests().await.unwrap()), ["POST", "DELETE"]);
+
+    // Only the failed cancellation is sent again
+    discord.reset().await;
+    Mock::given(method("POST")).respond_with(sent_message()).mount(&discord).await;
+    Mock::given(method("DELETE")).respond_with(ResponseTemplate::new(204)).mount(&discord).await;
+    assert!(run(dir.path()).await.status.success());
+    assert_eq!(methods(discord.received_requests().await.unwrap()), ["DELETE"]);
+}
+
+#[tokio::test]
+async fn an_announced_rotation_whose_node_breaks_is_not_cancelled() {
+    let now = chrono::Utc::now();
+    let schedules = common::schedules_with_big_run(now + chrono::TimeDelta::days(1), now + chrono::TimeDelta::days(3));
+    let splatoon3ink = common::splatoon3ink_serving(&schedules.to_string(), common::FESTIVALS_NEW).await;
+    let discord = MockServer::start().await;
+    Mock::given(method("POST")).respond_with(sent_message()).mount(&discord).await;
+    let dir = workdir(&splatoon3ink, &discord);
+    assert!(run(dir.path()).await.status.success());
+    let announced = fs::read_to_string(dir.path().join("Schedules Json.json")).unwrap();
+
+    // The Big Run's node stops matching the models for a while
+    let mut broken = schedules.clone();
+    common::big_run(&mut broken)["setting"]["weapons"] = Value::Null;
+    for served in [&broken, &schedules] {
+        common::serve_documents(&splatoon3ink, &served.to_string(), common::FESTIVALS_NEW).await;
+        discord.reset().await;
+        Mock::given(method("POST")).respond_with(sent_message()).mount(&discord).await;
+        Mock::given(method("DELETE")).respond_with(ResponseTemplate::new(204)).mount(&discord).await;
+        assert!(run(dir.path()).await.status.success());
+        assert!(discord.received_requests().await.unwrap().is_empty());
+        // The committed copy keeps the Big Run as it was announced
+        let committed: Value = serde_json::from_str(&fs::read_to_string(dir.path().join("Schedules Json.json")).unwrap()).unwrap();
+        let announced: Value = serde_json::from_str(&announced).unwrap();
+        assert_eq!(committed["data"]["coopGroupingSchedule"]["bigRunSchedules"], announced["data"]["coopGroupingSchedule"]["bigRunSchedules"]);
+    }
+    assert!(fs::read_to_string(dir.path().join("State Json.json")).unwrap().contains("\"messages\":{\"big_run:"));
+}
+
+#[tokio::test]
+async fn announcements_are_posted_in_full() {
+    let splatoon3ink = splatoon3ink().await;
+    let discord = MockServer::start().await;
+    Mock::given(method("POST")).respond_with(sent_message()).mount(&discord).await;
+    let dir = common::workdir(&splatoon3ink, &format!(
+        "webhook_url = \"{}{WEBHOOK_PATH}\"\n\
+        [kinds.big_run]\nrole = \"1111\"\nthumbnail = \"https://example.org/big_run.png\"\n\
+        [kinds.splatfest]\nrole = \"2222\"\nusername = \"Festival News\"\n",
+        discord.uri(),
+    ));
+    assert!(run(dir.path()).await.status.success());
+
+    let sent = posts(&discord.received_requests().await.unwrap());
+    let posted = |title: &str| sent.iter().find(|post| post["embeds"][0]["title"] == title).cloned().unwrap();
+    let expected = |fixture: &str| serde_json::from_str::<Value>(fixture).unwrap();
+    assert_eq!(posted("A Big Run alert has been broadcasted!"), expected(include_str!("fixtures/discord_big_run.json")));
+    assert_eq!(posted("A Splatfest has been announced!"), expected(include_str!("fixtures/discord_splatfest.json")));
+}
@@ -0,0 +1,115 @@
+//! Sends the same run to one destination per backend, each behind a local stand-in.
+
+mod common;
+
+use std::fs;
+
+use serde_json::json;
+use wiremock::{matchers::{header, method, path, path_regex}, Mock, MockServer, ResponseTemplate};
+
+use common::{bodies, run, schedules_with_ongoing_big_run, splatoon3ink, splatoon3ink_serving, workdir, EXPECTED_POSTS, FESTIVALS_NEW};
+
+const ROOM_PATH: &str = "/_matrix/client/v3/rooms/!room:example.org";
+
+#[tokio::test]
+async fn every_backend_receives_its_own_payload() {
+    let splatoon3ink = splatoon3ink_serving(&schedules_with_ongoing_big_run(), FESTIVALS_NEW).await;
+    let server = MockServer::start().await;
+    Mock::given(method("POST")).and(path("/discord"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "1000" })))
+        .mount(&server)
+        .await
+    ;
+    Mock::given(method("POST")).and(path("/slack"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+        .mount(&server)
+        .await
+    ;
+    Mock::given(method("PUT")).and(path_regex(r"/send/m\.room\.message/[^/]+$")).and(header("authorization", "Bearer secret"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "event_id": "$event" })))
+        .mount(&server)
+        .await
+    ;
+    Mock::given(method("POST")).and(path("/json"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&server)
+        .await
+    ;
+    let uri = server.uri();
+    let dir = workdir(&splatoon3ink, &format!(
+        "[[destinations]]\nname = \"discord\"\nwebhook_url = \"{uri}/discord\"\n\
+        [[destinations]]\nname = \"slack\"\nbackend = \"slack\"\nwebhook_url = \"{uri}/slack\"\n\
+        [[destinations]]\nname = \"matrix\"\nbackend = \"matrix\"\nwebhook_url = \"{uri}{ROOM_PATH}\"\naccess_token = \"secret\"\n\
+        [[destinations]]\nname = \"json\"\nbackend = \"json\"\nwebhook_url = \"{uri}/json\"\n",
+    ));
+
+    let output = run(dir.path()).await;
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&format!("Notifs sent: {} | Notifs failed: 0", 4 * EXPECTED_POSTS)));
+    let requests = server.received_requests().await.unwrap();
+    let to = |route: &str| requests.iter().filter(|request| request.url.path().starts_with(route)).count();
+    assert_eq!([to("/discord"), to("/slack"), to(ROOM_PATH), to("/json")], [EXPECTED_POSTS; 4]);
+
+    let slack: Vec<_> = requests.iter().filter(|request| request.url.path() == "/slack").map(|request| serde_json::from_slice::<serde_json::Value>(&request.body).unwrap()).collect();
+    let big_run = slack.iter().find(|message| message["blocks"][0]["text"]["text"] == "A Big Run alert has been broadcasted!").unwrap();
+    assert_eq!(big_run["blocks"][0]["type"], "header");
+    assert!(big_run["blocks"][1]["text"]["text"].as_str().unwrap().contains("<!date^"));
+    assert!(big_run["blocks"].as_array().unwrap().iter().any(|block| block["type"] == "image"));
+
+    let matrix = bodies(&requests, "PUT");
+    assert!(matrix.iter().all(|message| message["msgtype"] == "m.notice" && message["format"] == "org.matrix.custom.html"));
+
+    let json: Vec<_> = requests.iter().filter(|request| request.url.path() == "/json").map(|request| serde_json::from_slice::<serde_json::Value>(&request.body).unwrap()).collect();
+    let mut kinds: Vec<_> = json.iter().filter_map(|message| message["kind"].as_str()).collect();
+    kinds.sort();
+    assert_eq!(kinds, ["big_run", "eggstra_work", "random", "splatfest"]);
+
+    // Slack and JSON hand out no IDs, Discord and Matrix do, kept while the Big Run is on
+    let state = fs::read_to_string(dir.path().join("State Json.json")).unwrap();
+    assert_eq!(state.matches("\"1000\"").count(), 1);
+    assert_eq!(state.matches("\"$event\"").count(), 1);
+}
+
+#[tokio::test]
+async fn matrix_destinations_need_an_access_token() {
+    let splatoon3ink = splatoon3ink().await;
+    let dir = workdir(&splatoon3ink, &format!(
+        "[[destinations]]\nname = \"matrix\"\nbackend = \"matrix\"\nwebhook_url = \"http://localhost{ROOM_PATH}\"\n",
+    ));
+
+    let output = run(dir.path()).await;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("destinations.matrix.access_token"));
+}
+
+#[tokio::test]
+async fn matrix_retries_reuse_the_transaction_id() {
+    let splatoon3ink = splatoon3ink().await;
+    let server = MockServer::start().await;
+    Mock::given(method("PUT"))
+        .respond_with(ResponseTemplate::new(502).set_body_string("Bad Gateway"))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await
+    ;
+    Mock::given(method("PUT"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "event_id": "$event" })))
+        .mount(&server)
+        .await
+    ;
+    let dir = workdir(&splatoon3ink, &format!(
+        "[[destinations]]\nname = \"matrix\"\nbackend = \"matrix\"\nwebhook_url = \"{}{ROOM_PATH}\"\naccess_token = \"secret\"\n\
+        [sending]\nmax_retries = 1\nbackoff_ms = 1\n",
+        server.uri(),
+    ));
+
+    let output = run(dir.path()).await;
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&format!("Notifs sent: {EXPECTED_POSTS} | Notifs failed: 0")));
+    let requests = server.received_requests().await.unwrap();
+    let paths: Vec<_> = requests.iter().map(|request| request.url.path()).collect();
+    assert_eq!(paths.len(), EXPECTED_POSTS + 1);
+    // The failed first attempt was sent again under the same transaction ID
+    assert_eq!(paths[0], paths[1]);
+    assert_eq!(paths.iter().collect::<std::collections::HashSet<_>>().len(), EXPECTED_POSTS);
+}
@@ -0,0 +1,55 @@
+//! Runs the binary with `[state] backend = "sqlite"` and reads the history back.
+
+#![cfg(feature = "sqlite")]
+
+mod common;
+
+use std::fs;
+
+use serde_json::json;
+use splatink_discord_webhook::{fetch::{FESTIVALS_CACHE, SCHEDULES_CACHE}, sqlite::{SqliteStore, DATABASE_PATH}, state::StateStore};
+use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+use common::{run, run_with, splatoon3ink, workdir, EXPECTED_POSTS};
+
+#[tokio::test]
+async fn observations_and_deliveries_are_kept() {
+    let splatoon3ink = splatoon3ink().await;
+    let discord = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "1000" })))
+        .mount(&discord)
+        .await
+    ;
+    let dir = workdir(&splatoon3ink, &format!("webhook_url = \"{}/webhook\"\n[state]\nbackend = \"sqlite\"", discord.uri()));
+    // The database takes the place of the cache files
+    let store = SqliteStore::open(&dir.path().join(DATABASE_PATH)).unwrap();
+    for name in [SCHEDULES_CACHE, FESTIVALS_CACHE] {
+        store.commit_cache(name, &fs::read_to_string(dir.path().join(name)).unwrap()).unwrap();
+        fs::remove_file(dir.path().join(name)).unwrap();
+    }
+    drop(store);
+
+    let output = run(dir.path()).await;
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&format!("Notifs sent: {EXPECTED_POSTS}")));
+    assert!(!dir.path().join(SCHEDULES_CACHE).exists());
+
+    let output = run_with(dir.path(), &["history", "deliveries"]).await;
+    assert!(output.status.success());
+    let deliveries = String::from_utf8_lossy(&output.stdout);
+    let rows: Vec<_> = deliveries.lines().skip(1).collect();
+    assert_eq!(rows.len(), EXPECTED_POSTS);
+    assert!(rows.iter().all(|row| row.contains("\tposted\t0\t1000\t")));
+
+    let output = run_with(dir.path(), &["history", "events", "--kind", "splatfest"]).await;
+    let fests = String::from_utf8_lossy(&output.stdout);
+    assert!(fests.lines().count() > 1);
+    assert!(fests.lines().skip(1).all(|row| row.split('\t').nth(2) == Some("splatfest")));
+
+    // A second run sees the same events again without adding rows for them
+    let events = run_with(dir.path(), &["history", "events", "--limit", "1000"]).await.stdout;
+    run(dir.path()).await;
+    let again = run_with(dir.path(), &["history", "events", "--limit", "1000"]).await.stdout;
+    assert_eq!(String::from_utf8_lossy(&events).lines().count(), String::from_utf8_lossy(&again).lines().count());
+}
@@ -0,0 +1,55 @@
+//! Runs the daemon with the status server and reads its endpoints.
+
+mod common;
+
+use serde_json::{json, Value};
+use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+use common::{free_port, get_when_up, spawn, splatoon3ink, workdir};
+
+#[tokio::test]
+async fn status_endpoints_report_the_daemon() {
+    let splatoon3ink = splatoon3ink().await;
+    let discord = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(429).set_body_json(json!({ "message": "Slow down", "retry_after": 0.01, "global": false })))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&discord)
+        .await
+    ;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "1000" })))
+        .mount(&discord)
+        .await
+    ;
+    let port = free_port();
+    let dir = workdir(&splatoon3ink, &format!("webhook_url = \"{}/webhook\"\n[status]\nlisten = \"127.0.0.1:{port}\"", discord.uri()));
+    let _daemon = spawn(dir.path(), &["--daemon"]);
+    let base = format!("http://127.0.0.1:{port}");
+
+    // The server comes up before the first run has finished
+    let mut response = get_when_up(&format!("{base}/healthz")).await;
+    let mut health: Value = Value::Null;
+    for _ in 0..50 {
+        let ok = response.status() == 200;
+        health = response.json().await.unwrap();
+        if ok && !health["last_post"].is_null() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        response = reqwest::get(format!("{base}/healthz")).await.unwrap();
+    }
+    assert_eq!(health["status"], "ok");
+    assert!(health["last_post"].is_string());
+    assert!(health["sources"]["schedules"]["last_success"].is_string());
+    assert!(health["sources"]["festivals"]["last_success"].is_string());
+
+    let metrics = reqwest::get(format!("{base}/metrics")).await.unwrap().text().await.unwrap();
+    assert!(metrics.contains("splatink_fetch_duration_seconds_count{source=\"schedules\"} 1"));
+    assert!(metrics.contains("splatink_notifications_sent_total{kind=\"big_run\"} 1"));
+    assert!(metrics.contains("splatink_rate_limited_total 1"));
+
+    let state: Value = reqwest::get(format!("{base}/state")).await.unwrap().json().await.unwrap();
+    assert!(state["data"]["coopGroupingSchedule"].is_object());
+}
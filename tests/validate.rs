@@ -0,0 +1,32 @@
+//! Runs `validate` on broken copies of the fixtures.
+
+mod common;
+
+use std::fs;
+
+use serde_json::Value;
+
+use common::run_with;
+
+#[tokio::test]
+async fn broken_paths_are_reported_without_a_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut festivals: Value = serde_json::from_str(include_str!("fixtures/festivals_new.json")).unwrap();
+    festivals["EU"]["data"]["festRecords"]["nodes"][1]["teams"][0]["color"] = Value::from("red");
+    festivals["EU"]["data"]["festRecords"]["nodes"][1]["sponsor"] = Value::from("splatoon3.ink");
+    fs::write(dir.path().join("festivals.json"), festivals.to_string()).unwrap();
+
+    let output = run_with(dir.path(), &["validate", "festivals", "festivals.json"]).await;
+    assert!(!output.status.success());
+    let lines: Vec<_> = String::from_utf8_lossy(&output.stdout).lines().map(str::to_owned).collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("broken  EU.data.festRecords.nodes[1].teams[0].color: invalid type: string \"red\""), "{}", lines[0]);
+    assert_eq!(lines[1], "1 nodes skipped, 0 unknown fields");
+
+    // Fine apart from a field the models don't know
+    festivals["EU"]["data"]["festRecords"]["nodes"][1]["teams"][0]["color"] = serde_json::json!({ "a": 1, "r": 1, "g": 0, "b": 0 });
+    fs::write(dir.path().join("festivals.json"), festivals.to_string()).unwrap();
+    let output = run_with(dir.path(), &["validate", "festivals", "festivals.json"]).await;
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("unknown EU.data.festRecords.nodes[1].sponsor\n"));
+}